@@ -0,0 +1,82 @@
+//! Compares the spatial indexes on the workload of one simulation step:
+//! rebuild from scratch, then find the neighbours of every particle within
+//! the interaction radius.
+//!
+//! ```text
+//! cargo bench --bench spatial_index
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use particle_life_simulator::simulation::{Bounds, PhysicsParams, SpatialIndexKind};
+use particle_life_simulator::utils::math::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const COUNTS: [usize; 3] = [1_000, 8_000, 50_000];
+
+// World size for `count` particles at the density of preset 5 (8000 in 1200 x 800)
+fn bounds_for(count: usize) -> Bounds {
+    let scale = (count as f32 / 8000.0).sqrt();
+    Bounds {
+        x: 0.0,
+        y: 0.0,
+        width: 1200.0 * scale,
+        height: 800.0 * scale,
+    }
+}
+
+fn random_positions(count: usize, bounds: Bounds) -> Vec<Vec2> {
+    let mut rng = StdRng::seed_from_u64(count as u64);
+    (0..count)
+        .map(|_| {
+            Vec2::new(
+                rng.gen_range(bounds.x..bounds.x + bounds.width),
+                rng.gen_range(bounds.y..bounds.y + bounds.height),
+            )
+        })
+        .collect()
+}
+
+fn bench_spatial_index(c: &mut Criterion) {
+    let radius = PhysicsParams::default().interaction_radius;
+
+    let mut build = c.benchmark_group("build");
+    for count in COUNTS {
+        let bounds = bounds_for(count);
+        let positions = random_positions(count, bounds);
+        for kind in SpatialIndexKind::ALL {
+            let mut index = kind.create(bounds, radius);
+            build.bench_with_input(BenchmarkId::new(kind.name(), count), &positions, |b, positions| {
+                b.iter(|| index.build(black_box(positions)))
+            });
+        }
+    }
+    build.finish();
+
+    let mut step = c.benchmark_group("build_and_query");
+    step.sample_size(10);
+    for count in COUNTS {
+        let bounds = bounds_for(count);
+        let positions = random_positions(count, bounds);
+        for kind in SpatialIndexKind::ALL {
+            let mut index = kind.create(bounds, radius);
+            let mut found = Vec::new();
+            step.bench_with_input(BenchmarkId::new(kind.name(), count), &positions, |b, positions| {
+                b.iter(|| {
+                    index.build(positions);
+                    let mut total = 0;
+                    for &position in positions {
+                        found.clear();
+                        index.neighbors_within(position, radius, &mut found);
+                        total += found.len();
+                    }
+                    black_box(total)
+                })
+            });
+        }
+    }
+    step.finish();
+}
+
+criterion_group!(benches, bench_spatial_index);
+criterion_main!(benches);
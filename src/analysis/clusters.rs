@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::boundary::wrap_position;
+use crate::simulation::{BoundaryMode, Bounds, CellList, SpatialIndex, World};
+use crate::utils::math::Vec2;
+
+/// How `find_clusters` groups particles.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClusterParams {
+    /// Particles closer than this are linked; a cluster is a connected
+    /// group of links.
+    pub link_distance: f32,
+    /// Groups with fewer particles are treated as noise and not reported.
+    pub min_size: usize,
+}
+
+impl Default for ClusterParams {
+    fn default() -> Self {
+        Self {
+            link_distance: 15.0,
+            min_size: 5,
+        }
+    }
+}
+
+/// A connected group of particles and its bulk properties. Distances and
+/// angular momentum use the minimum image on a wrapping world.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cluster {
+    /// Stable across frames once assigned by a `ClusterTracker`; 0 before.
+    pub id: u64,
+    /// Particle indices, ascending.
+    pub members: Vec<usize>,
+    /// Member count per species id.
+    pub species_counts: Vec<usize>,
+    pub mass: f32,
+    /// Mass-weighted centre.
+    pub centroid: Vec2,
+    /// Root mean square distance of the members from the centroid.
+    pub radius: f32,
+    /// Velocity of the centre of mass.
+    pub velocity: Vec2,
+    /// Angular momentum about the centroid, relative to the centre of mass
+    /// motion. Positive is counter-clockwise in world coordinates.
+    pub angular_momentum: f32,
+}
+
+impl Cluster {
+    pub fn size(&self) -> usize {
+        self.members.len()
+    }
+}
+
+/// Groups the particles of `world` into clusters by linking every pair
+/// within `params.link_distance`. Clusters are ordered by their first member.
+pub fn find_clusters(world: &World, params: &ClusterParams) -> Vec<Cluster> {
+    let particles = world.get_particles();
+    let bounds = Bounds {
+        x: 0.0,
+        y: 0.0,
+        width: world.width(),
+        height: world.height(),
+    };
+    let wraps = world.get_physics().boundary == BoundaryMode::Wrap;
+    let positions: Vec<Vec2> = particles.iter().map(|particle| particle.position).collect();
+    let mut index = CellList::new(bounds, params.link_distance);
+    index.build(&positions);
+
+    let mut groups = UnionFind::new(particles.len());
+    let mut neighbors = Vec::new();
+    for (i, &position) in positions.iter().enumerate() {
+        neighbors.clear();
+        if wraps {
+            index.neighbors_within_periodic(position, params.link_distance, bounds, &mut neighbors);
+        } else {
+            index.neighbors_within(position, params.link_distance, &mut neighbors);
+        }
+        for &j in &neighbors {
+            if j > i {
+                groups.union(i, j);
+            }
+        }
+    }
+
+    // Members in index order, keyed by each group's first member
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut first_of_root = vec![usize::MAX; particles.len()];
+    for i in 0..particles.len() {
+        let root = groups.find(i);
+        if first_of_root[root] == usize::MAX {
+            first_of_root[root] = i;
+        }
+        members.entry(first_of_root[root]).or_default().push(i);
+    }
+
+    members
+        .into_values()
+        .filter(|members| members.len() >= params.min_size.max(1))
+        .map(|members| measure(world, members, wraps))
+        .collect()
+}
+
+fn measure(world: &World, members: Vec<usize>, wraps: bool) -> Cluster {
+    let particles = world.get_particles();
+    let mut species_counts = vec![0; world.get_species().len()];
+    let mut mass = 0.0;
+    let mut momentum = Vec2::new(0.0, 0.0);
+    // Offsets from the first member, so a cluster straddling the seam of a
+    // wrapping world isn't split across it
+    let origin = particles[members[0]].position;
+    let mut weighted_offset = Vec2::new(0.0, 0.0);
+    for &i in &members {
+        let particle = &particles[i];
+        if let Some(count) = species_counts.get_mut(particle.species as usize) {
+            *count += 1;
+        }
+        mass += particle.mass;
+        momentum += particle.velocity * particle.mass;
+        weighted_offset += world.displacement(origin, particle.position) * particle.mass;
+    }
+    let mut centroid = origin + weighted_offset * (1.0 / mass);
+    if wraps {
+        centroid = wrap_position(centroid, world.width(), world.height());
+    }
+    let velocity = momentum * (1.0 / mass);
+
+    let mut spread = 0.0;
+    let mut angular_momentum = 0.0;
+    for &i in &members {
+        let particle = &particles[i];
+        let r = world.displacement(centroid, particle.position);
+        let v = particle.velocity - velocity;
+        spread += r.length_squared();
+        angular_momentum += particle.mass * (r.x * v.y - r.y * v.x);
+    }
+
+    Cluster {
+        id: 0,
+        radius: (spread / members.len() as f32).sqrt(),
+        members,
+        species_counts,
+        mass,
+        centroid,
+        velocity,
+        angular_momentum,
+    }
+}
+
+// Disjoint sets with path halving and union by size
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(count: usize) -> Self {
+        Self {
+            parent: (0..count).collect(),
+            size: vec![1; count],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+/// Something that happened to the tracked clusters between two frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClusterEvent {
+    /// A cluster with no members in common with any previous one.
+    Born { id: u64 },
+    /// A cluster that was not continued; `lifetime` is in frames.
+    Ended { id: u64, lifetime: u64 },
+    /// Several clusters mostly joined `into`. `from` lists them largest
+    /// contribution first; `into` keeps the ID of the first.
+    Merged { into: u64, from: Vec<u64> },
+    /// A cluster whose members now form several, each keeping at least
+    /// `ClusterParams::min_size` of them. `from` carries on as one of `into`.
+    Split { from: u64, into: Vec<u64> },
+}
+
+/// Gives clusters stable IDs across frames by matching their members.
+///
+/// A new cluster continues the previous cluster that sent it the most
+/// members, provided that cluster sent more of its members there than
+/// anywhere else. Particles are identified by index, so removing particles
+/// (absorbing walls) between frames confuses the matching.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterTracker {
+    frame: u64,
+    next_id: u64,
+    // Cluster ID of each particle in the previous frame
+    membership: Vec<Option<u64>>,
+    // Frame each live cluster was first seen in
+    born: BTreeMap<u64, u64>,
+    min_split_size: usize,
+}
+
+impl ClusterTracker {
+    /// `min_split_size` is the smallest piece that counts towards a split,
+    /// usually `ClusterParams::min_size`.
+    pub fn new(min_split_size: usize) -> Self {
+        Self {
+            next_id: 1,
+            min_split_size,
+            ..Self::default()
+        }
+    }
+
+    /// Frames passed to `update` so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Frames the live cluster `id` has existed for.
+    pub fn age(&self, id: u64) -> Option<u64> {
+        self.born.get(&id).map(|&born| self.frame - born)
+    }
+
+    /// Assigns IDs to the clusters of the next frame and returns what
+    /// changed since the previous one.
+    pub fn update(&mut self, clusters: &mut [Cluster]) -> Vec<ClusterEvent> {
+        self.frame += 1;
+
+        // overlap[c][p]: members of new cluster c that were in previous cluster p
+        let overlap: Vec<BTreeMap<u64, usize>> = clusters
+            .iter()
+            .map(|cluster| {
+                let mut counts = BTreeMap::new();
+                for &i in &cluster.members {
+                    if let Some(Some(previous)) = self.membership.get(i) {
+                        *counts.entry(*previous).or_insert(0) += 1;
+                    }
+                }
+                counts
+            })
+            .collect();
+
+        // Where each previous cluster sent most of its members
+        let mut successor: BTreeMap<u64, (usize, usize)> = BTreeMap::new();
+        for (c, counts) in overlap.iter().enumerate() {
+            for (&previous, &count) in counts {
+                let best = successor.entry(previous).or_insert((c, count));
+                if count > best.1 {
+                    *best = (c, count);
+                }
+            }
+        }
+
+        let mut events = Vec::new();
+        let mut continued = BTreeSet::new();
+        for (c, cluster) in clusters.iter_mut().enumerate() {
+            // Previous clusters that mostly ended up here, largest share first
+            let mut predecessors: Vec<(u64, usize)> = overlap[c]
+                .iter()
+                .filter(|&(previous, _)| successor[previous].0 == c)
+                .map(|(&previous, &count)| (previous, count))
+                .collect();
+            predecessors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+            cluster.id = match predecessors.first() {
+                Some(&(id, _)) => id,
+                None => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    self.born.insert(id, self.frame);
+                    if overlap[c].is_empty() {
+                        events.push(ClusterEvent::Born { id });
+                    }
+                    id
+                }
+            };
+            continued.insert(cluster.id);
+            if predecessors.len() > 1 {
+                events.push(ClusterEvent::Merged {
+                    into: cluster.id,
+                    from: predecessors.iter().map(|&(id, _)| id).collect(),
+                });
+            }
+        }
+
+        // Splits: previous clusters with sizable pieces in several new ones
+        for previous in successor.keys() {
+            let pieces: Vec<u64> = clusters
+                .iter()
+                .enumerate()
+                .filter(|(c, _)| overlap[*c].get(previous).is_some_and(|&count| count >= self.min_split_size))
+                .map(|(_, cluster)| cluster.id)
+                .collect();
+            if pieces.len() > 1 {
+                events.push(ClusterEvent::Split {
+                    from: *previous,
+                    into: pieces,
+                });
+            }
+        }
+
+        // Clusters that weren't continued have ended
+        let ended: Vec<u64> = self.born.keys().copied().filter(|id| !continued.contains(id)).collect();
+        for id in ended {
+            let born = self.born.remove(&id).unwrap_or(self.frame);
+            events.push(ClusterEvent::Ended {
+                id,
+                lifetime: self.frame - born,
+            });
+        }
+
+        let particle_count = clusters
+            .iter()
+            .flat_map(|cluster| cluster.members.last())
+            .max()
+            .map_or(0, |&last| last + 1);
+        self.membership = vec![None; particle_count];
+        for cluster in clusters.iter() {
+            for &i in &cluster.members {
+                self.membership[i] = Some(cluster.id);
+            }
+        }
+
+        events
+    }
+}
@@ -0,0 +1,4 @@
+pub mod clusters;
+pub mod rdf;
+pub use clusters::{find_clusters, Cluster, ClusterEvent, ClusterParams, ClusterTracker};
+pub use rdf::{PairRdf, RadialDistribution, Rdf, RdfParams};
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::{BoundaryMode, Bounds, World};
+
+/// Range and resolution of a radial distribution function.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RdfParams {
+    /// Largest separation measured. On a wrapping world keep it under half
+    /// the smaller side, beyond which the minimum image hides pairs.
+    pub max_radius: f32,
+    pub bins: usize,
+}
+
+impl Default for RdfParams {
+    fn default() -> Self {
+        Self {
+            max_radius: 100.0,
+            bins: 50,
+        }
+    }
+}
+
+/// Pair correlation g(r) for every species pair, averaged over the samples
+/// taken. 1 means particles of `target` are found around particles of
+/// `source` at that distance as often as in a uniform gas of the same
+/// density; above 1 they gather there, below 1 they avoid it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rdf {
+    pub samples: usize,
+    pub bin_width: f32,
+    /// Centre of each bin.
+    pub radii: Vec<f32>,
+    /// One entry per pair with `source <= target`.
+    pub pairs: Vec<PairRdf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairRdf {
+    pub source: usize,
+    pub target: usize,
+    pub g: Vec<f32>,
+}
+
+/// Accumulates pair distance histograms over samples of a world, to average
+/// g(r) over a time window.
+///
+/// With walls (reflect or absorb boundaries), only particles at least
+/// `max_radius` from every wall serve as centres, so no shell is cut off by
+/// the edge of the world. On a wrapping world every particle does and
+/// distances use the minimum image.
+#[derive(Debug, Clone)]
+pub struct RadialDistribution {
+    params: RdfParams,
+    species_count: usize,
+    samples: usize,
+    // counts[(source * species_count + target) * bins + bin]: pairs seen
+    counts: Vec<f64>,
+    // Per pair: Σ over samples of centres × density of targets, the number of
+    // pairs per unit area a uniform gas would give
+    expected_density: Vec<f64>,
+}
+
+impl RadialDistribution {
+    pub fn new(params: RdfParams, species_count: usize) -> Self {
+        let params = RdfParams {
+            bins: params.bins.max(1),
+            ..params
+        };
+        Self {
+            params,
+            species_count,
+            samples: 0,
+            counts: vec![0.0; species_count * species_count * params.bins],
+            expected_density: vec![0.0; species_count * species_count],
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Starts a new averaging window.
+    pub fn reset(&mut self) {
+        self.samples = 0;
+        self.counts.fill(0.0);
+        self.expected_density.fill(0.0);
+    }
+
+    /// Adds the current state of `world` to the average. Particles of
+    /// species beyond `species_count` are ignored.
+    pub fn add_sample(&mut self, world: &World) {
+        let (width, height) = (world.width(), world.height());
+        let bounds = Bounds {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        };
+        let max_radius = self.params.max_radius;
+        let bins = self.params.bins;
+        let bin_width = max_radius / bins as f32;
+        let species_count = self.species_count;
+        let wraps = world.get_physics().boundary == BoundaryMode::Wrap;
+
+        let particles = world.get_particles();
+        let positions: Vec<_> = particles.iter().map(|particle| particle.position).collect();
+        let mut index = world.get_physics().spatial_index.create(bounds, max_radius);
+        index.build(&positions);
+
+        let mut population = vec![0usize; species_count];
+        let mut centres = vec![0usize; species_count];
+        let mut neighbors = Vec::new();
+        for (i, particle) in particles.iter().enumerate() {
+            let source = particle.species as usize;
+            if source >= species_count {
+                continue;
+            }
+            population[source] += 1;
+
+            let position = particle.position;
+            if !wraps
+                && (position.x < max_radius
+                    || position.y < max_radius
+                    || position.x > width - max_radius
+                    || position.y > height - max_radius)
+            {
+                continue;
+            }
+            centres[source] += 1;
+
+            neighbors.clear();
+            if wraps {
+                index.neighbors_within_periodic(position, max_radius, bounds, &mut neighbors);
+            } else {
+                index.neighbors_within(position, max_radius, &mut neighbors);
+            }
+            for &j in &neighbors {
+                let target = particles[j].species as usize;
+                if j == i || target >= species_count {
+                    continue;
+                }
+                let distance = world.displacement(position, particles[j].position).length();
+                let bin = (distance / bin_width) as usize;
+                if bin < bins {
+                    self.counts[(source * species_count + target) * bins + bin] += 1.0;
+                }
+            }
+        }
+
+        let area = (width * height) as f64;
+        for (source, &centre_count) in centres.iter().enumerate() {
+            for (target, &target_count) in population.iter().enumerate() {
+                // A particle is never its own neighbour
+                let others = target_count - usize::from(source == target && target_count > 0);
+                self.expected_density[source * species_count + target] += centre_count as f64 * others as f64 / area;
+            }
+        }
+        self.samples += 1;
+    }
+
+    /// g(r) averaged over the samples so far. Pairs never seen (no centres or
+    /// no targets) are all zero.
+    pub fn result(&self) -> Rdf {
+        let bins = self.params.bins;
+        let bin_width = self.params.max_radius / bins as f32;
+        let shell_area = |bin: usize| {
+            let (inner, outer) = (bin as f64 * bin_width as f64, (bin + 1) as f64 * bin_width as f64);
+            std::f64::consts::PI * (outer * outer - inner * inner)
+        };
+
+        let mut pairs = Vec::new();
+        for source in 0..self.species_count {
+            for target in source..self.species_count {
+                // Both directions of an unlike pair measure the same thing;
+                // pool them
+                let directions: &[(usize, usize)] = if source == target {
+                    &[(source, target)]
+                } else {
+                    &[(source, target), (target, source)]
+                };
+                let expected: f64 = directions
+                    .iter()
+                    .map(|&(a, b)| self.expected_density[a * self.species_count + b])
+                    .sum();
+                let g = (0..bins)
+                    .map(|bin| {
+                        let count: f64 = directions
+                            .iter()
+                            .map(|&(a, b)| self.counts[(a * self.species_count + b) * bins + bin])
+                            .sum();
+                        if expected > 0.0 {
+                            (count / (expected * shell_area(bin))) as f32
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect();
+                pairs.push(PairRdf { source, target, g });
+            }
+        }
+
+        Rdf {
+            samples: self.samples,
+            bin_width,
+            radii: (0..bins).map(|bin| (bin as f32 + 0.5) * bin_width).collect(),
+            pairs,
+        }
+    }
+}
@@ -0,0 +1,615 @@
+//! Runs the simulation without a window: builds a `World` from a preset or a
+//! snapshot, steps it a fixed number of times with a fixed `dt`, and writes
+//! snapshots and/or per-step diagnostics.
+//!
+//! ```text
+//! particle-life-headless [--preset N | --preset-file PATH | --snapshot PATH] [--seed N]
+//!                        [--steps N] [--dt SECONDS] [--width W] [--height H]
+//!                        [--spatial-index quadtree|cells] [--boundary reflect|wrap|absorb]
+//!                        [--kernel linear|piecewise|inverse_square|lennard_jones]
+//!                        [--integrator euler|verlet|rk4] [--friction-half-life SECONDS|none]
+//!                        [--restitution E] [--collision-iterations N]
+//!                        [--random-matrix uniform|symmetric|antisymmetric|sparse|diagonal_repulsive]
+//!                        [--matrix-seed N]
+//!                        [--compare-integrators]
+//!                        [--snapshot-every K] [--snapshot-format json|bin]
+//!                        [--output-dir DIR] [--stats PATH|-] [--stats-every K]
+//!                        [--stats-format csv|jsonl]
+//!                        [--clusters PATH|-] [--clusters-every K]
+//!                        [--cluster-link-distance D] [--cluster-min-size N]
+//!                        [--rdf PATH|-] [--rdf-every K] [--rdf-window N]
+//!                        [--rdf-max-radius R] [--rdf-bins N]
+//! ```
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use particle_life_simulator::analysis::{
+    find_clusters, ClusterEvent, ClusterParams, ClusterTracker, RadialDistribution, Rdf, RdfParams,
+};
+use particle_life_simulator::simulation::integration::compare_integrators;
+use particle_life_simulator::simulation::{
+    BoundaryMode, Diagnostics, Integrator, Kernel, MatrixDistribution, Preset, SpatialIndexKind, World,
+};
+use particle_life_simulator::utils::math::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+const USAGE: &str = "\
+Usage: particle-life-headless [options]
+
+World:
+  --preset N             Start from built-in preset N (default 1)
+  --preset-file PATH     Start from a JSON preset file
+  --snapshot PATH        Start from a snapshot (.bin = binary, otherwise JSON)
+  --seed N               RNG seed (default: random, printed on start)
+  --width W --height H   World size for presets (default 1200 x 800)
+
+Run:
+  --steps N              Number of steps (default 1000)
+  --dt SECONDS           Fixed time step (default 0.016)
+  --spatial-index KIND   quadtree or cells (default: the preset's or snapshot's)
+  --boundary MODE        reflect, wrap or absorb (default: the preset's or snapshot's)
+  --kernel NAME          linear, piecewise, inverse_square or lennard_jones with default
+                         parameters (default: the preset's or snapshot's)
+  --integrator NAME      euler, verlet or rk4 (default: the preset's or snapshot's)
+  --friction-half-life S Seconds for friction to halve speeds, or none (default: the
+                         preset's or snapshot's)
+  --restitution E        Bounciness of particle collisions, 0 to 1 (default: the preset's
+                         or snapshot's)
+  --collision-iterations N
+                         Collision solver passes per step (default: the preset's or
+                         snapshot's)
+  --random-matrix NAME   Replace the interaction strengths with random ones: uniform,
+                         symmetric, antisymmetric, sparse or diagonal_repulsive
+  --matrix-seed N        Seed for --random-matrix (default: drawn from the world's RNG,
+                         printed on start)
+  --compare-integrators  Instead of a normal run, run every integrator from the same
+                         start without friction and print the energy drift of each
+
+Output:
+  --output-dir DIR       Directory for snapshots (default output)
+  --snapshot-every K     Write a snapshot every K steps (default 0 = final state only)
+  --snapshot-format F    json or bin (default json)
+  --no-snapshots         Don't write any snapshots
+  --stats PATH           Write per-step diagnostics (energy, momentum, temperature, ...)
+                         to PATH, or - for stdout
+  --stats-every K        Diagnostics interval in steps (default 1)
+  --stats-format F       csv, or jsonl for one JSON object per line that also has the
+                         per-species speed histograms (default csv)
+  --clusters PATH        Write the clusters and their births, merges, splits and ends as
+                         JSON lines to PATH, or - for stdout
+  --clusters-every K     Cluster interval in steps (default 10)
+  --cluster-link-distance D
+                         Particles closer than D belong to the same cluster (default 15)
+  --cluster-min-size N   Smallest group reported as a cluster (default 5)
+  --rdf PATH             Write the radial distribution function g(r) of every species
+                         pair as JSON lines to PATH, or - for stdout
+  --rdf-every K          Sample g(r) every K steps (default 10)
+  --rdf-window N         Samples averaged into each line written (default 10)
+  --rdf-max-radius R     Largest distance measured (default 100)
+  --rdf-bins N           Histogram bins (default 50)
+";
+
+struct Options {
+    preset: u32,
+    preset_file: Option<PathBuf>,
+    snapshot: Option<PathBuf>,
+    seed: Option<u64>,
+    width: f32,
+    height: f32,
+    steps: u64,
+    dt: f32,
+    spatial_index: Option<SpatialIndexKind>,
+    boundary: Option<BoundaryMode>,
+    kernel: Option<Kernel>,
+    integrator: Option<Integrator>,
+    friction_half_life: Option<Option<f32>>,
+    restitution: Option<f32>,
+    collision_iterations: Option<u32>,
+    random_matrix: Option<MatrixDistribution>,
+    matrix_seed: Option<u64>,
+    compare_integrators: bool,
+    output_dir: PathBuf,
+    snapshot_every: u64,
+    snapshot_extension: &'static str,
+    write_snapshots: bool,
+    stats: Option<String>,
+    stats_every: u64,
+    stats_format: StatsFormat,
+    clusters: Option<String>,
+    clusters_every: u64,
+    cluster_params: ClusterParams,
+    rdf: Option<String>,
+    rdf_every: u64,
+    rdf_window: usize,
+    rdf_params: RdfParams,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StatsFormat {
+    Csv,
+    JsonLines,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            preset: 1,
+            preset_file: None,
+            snapshot: None,
+            seed: None,
+            width: 1200.0,
+            height: 800.0,
+            steps: 1000,
+            dt: 0.016,
+            spatial_index: None,
+            boundary: None,
+            kernel: None,
+            integrator: None,
+            friction_half_life: None,
+            restitution: None,
+            collision_iterations: None,
+            random_matrix: None,
+            matrix_seed: None,
+            compare_integrators: false,
+            output_dir: PathBuf::from("output"),
+            snapshot_every: 0,
+            snapshot_extension: "json",
+            write_snapshots: true,
+            stats: None,
+            stats_every: 1,
+            stats_format: StatsFormat::Csv,
+            clusters: None,
+            clusters_every: 10,
+            cluster_params: ClusterParams::default(),
+            rdf: None,
+            rdf_every: 10,
+            rdf_window: 10,
+            rdf_params: RdfParams::default(),
+        }
+    }
+}
+
+/// Parses the arguments after the program name. Returns `None` when help
+/// was asked for.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--preset" => options.preset = parse(&arg, &value()?)?,
+            "--preset-file" => options.preset_file = Some(PathBuf::from(value()?)),
+            "--snapshot" => options.snapshot = Some(PathBuf::from(value()?)),
+            "--seed" => options.seed = Some(parse(&arg, &value()?)?),
+            "--width" => options.width = parse_size(&arg, &value()?)?,
+            "--height" => options.height = parse_size(&arg, &value()?)?,
+            "--steps" => options.steps = parse(&arg, &value()?)?,
+            "--dt" => options.dt = parse(&arg, &value()?)?,
+            "--spatial-index" => {
+                let name = value()?;
+                options.spatial_index = Some(
+                    SpatialIndexKind::from_name(&name)
+                        .ok_or_else(|| format!("unknown spatial index {}", name))?,
+                )
+            }
+            "--boundary" => {
+                let name = value()?;
+                options.boundary = Some(
+                    BoundaryMode::from_name(&name).ok_or_else(|| format!("unknown boundary mode {}", name))?,
+                )
+            }
+            "--kernel" => {
+                let name = value()?;
+                options.kernel = Some(Kernel::from_name(&name).ok_or_else(|| format!("unknown kernel {}", name))?)
+            }
+            "--random-matrix" => {
+                let name = value()?;
+                options.random_matrix = Some(
+                    MatrixDistribution::from_name(&name).ok_or_else(|| format!("unknown matrix distribution {}", name))?,
+                )
+            }
+            "--matrix-seed" => options.matrix_seed = Some(parse(&arg, &value()?)?),
+            "--integrator" => {
+                let name = value()?;
+                options.integrator =
+                    Some(Integrator::from_name(&name).ok_or_else(|| format!("unknown integrator {}", name))?)
+            }
+            "--friction-half-life" => {
+                options.friction_half_life = match value()?.as_str() {
+                    "none" => Some(None),
+                    seconds => Some(Some(parse(&arg, seconds)?)),
+                }
+            }
+            "--restitution" => options.restitution = Some(parse(&arg, &value()?)?),
+            "--collision-iterations" => options.collision_iterations = Some(parse(&arg, &value()?)?),
+            "--compare-integrators" => options.compare_integrators = true,
+            "--output-dir" => options.output_dir = PathBuf::from(value()?),
+            "--snapshot-every" => options.snapshot_every = parse(&arg, &value()?)?,
+            "--snapshot-format" => {
+                options.snapshot_extension = match value()?.as_str() {
+                    "json" => "json",
+                    "bin" => "bin",
+                    other => return Err(format!("unknown snapshot format {}", other)),
+                }
+            }
+            "--no-snapshots" => options.write_snapshots = false,
+            "--stats" => options.stats = Some(value()?),
+            "--stats-every" => options.stats_every = parse::<u64>(&arg, &value()?)?.max(1),
+            "--stats-format" => {
+                options.stats_format = match value()?.as_str() {
+                    "csv" => StatsFormat::Csv,
+                    "jsonl" => StatsFormat::JsonLines,
+                    other => return Err(format!("unknown stats format {}", other)),
+                }
+            }
+            "--clusters" => options.clusters = Some(value()?),
+            "--clusters-every" => options.clusters_every = parse::<u64>(&arg, &value()?)?.max(1),
+            "--cluster-link-distance" => options.cluster_params.link_distance = parse(&arg, &value()?)?,
+            "--cluster-min-size" => options.cluster_params.min_size = parse(&arg, &value()?)?,
+            "--rdf" => options.rdf = Some(value()?),
+            "--rdf-every" => options.rdf_every = parse::<u64>(&arg, &value()?)?.max(1),
+            "--rdf-window" => options.rdf_window = parse::<usize>(&arg, &value()?)?.max(1),
+            "--rdf-max-radius" => options.rdf_params.max_radius = parse(&arg, &value()?)?,
+            "--rdf-bins" => options.rdf_params.bins = parse(&arg, &value()?)?,
+            "--help" | "-h" => return Ok(None),
+            other => return Err(format!("unknown argument {}", other)),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("invalid value {:?} for {}: {}", value, flag, e))
+}
+
+// World sizes must be positive, or spawning particles panics
+fn parse_size(flag: &str, value: &str) -> Result<f32, String> {
+    let size: f32 = parse(flag, value)?;
+    if size.is_finite() && size > 0.0 {
+        Ok(size)
+    } else {
+        Err(format!("invalid value {:?} for {}: must be a positive number", value, flag))
+    }
+}
+
+fn build_world(options: &Options) -> Result<World, Box<dyn Error>> {
+    let mut world = match options.seed {
+        Some(seed) => World::new_with_seed(options.width, options.height, seed),
+        None => World::new(options.width, options.height),
+    };
+    match (&options.snapshot, &options.preset_file) {
+        (Some(path), _) => world.load_snapshot(path)?,
+        (None, Some(path)) => world.apply_preset(&Preset::load(path)?)?,
+        (None, None) => world.load_preset(options.preset)?,
+    }
+    if let Some(kind) = options.spatial_index {
+        world.get_physics_mut().spatial_index = kind;
+    }
+    if let Some(mode) = options.boundary {
+        world.get_physics_mut().boundary = mode;
+    }
+    if let Some(kernel) = options.kernel {
+        world.get_physics_mut().kernel = kernel;
+    }
+    if let Some(integrator) = options.integrator {
+        world.get_physics_mut().integrator = integrator;
+    }
+    if let Some(half_life) = options.friction_half_life {
+        world.get_physics_mut().friction_half_life = half_life;
+    }
+    if let Some(restitution) = options.restitution {
+        world.get_physics_mut().restitution = restitution;
+    }
+    if let Some(iterations) = options.collision_iterations {
+        world.get_physics_mut().collision_iterations = iterations;
+    }
+    world.get_physics().check().map_err(|reason| format!("invalid physics: {}", reason))?;
+    if let Some(distribution) = options.random_matrix {
+        let seed = options.matrix_seed.unwrap_or_else(|| world.rng_mut().gen());
+        world
+            .get_interaction_matrix_mut()
+            .randomize(&mut StdRng::seed_from_u64(seed), distribution);
+        eprintln!("Random {} matrix with seed {}", distribution.name(), seed);
+    }
+    Ok(world)
+}
+
+fn write_stats_header(out: &mut dyn Write, world: &World, format: StatsFormat) -> io::Result<()> {
+    if format == StatsFormat::JsonLines {
+        return Ok(());
+    }
+    write!(
+        out,
+        "step,time,particles,kinetic_energy,momentum_x,momentum_y,mean_speed,centre_x,centre_y,temperature"
+    )?;
+    for (_, species) in world.get_species().iter() {
+        write!(out, ",count_{0},mean_speed_{0},speed_std_dev_{0}", species.name)?;
+    }
+    writeln!(out)
+}
+
+// One line of `--stats-format jsonl`
+#[derive(Serialize)]
+struct StatsLine<'a> {
+    step: u64,
+    time: f64,
+    #[serde(flatten)]
+    diagnostics: &'a Diagnostics,
+}
+
+fn write_stats_row(out: &mut dyn Write, world: &World, format: StatsFormat, step: u64, time: f64) -> io::Result<()> {
+    let diagnostics = world.diagnostics();
+    if format == StatsFormat::JsonLines {
+        let line = StatsLine {
+            step,
+            time,
+            diagnostics: &diagnostics,
+        };
+        serde_json::to_writer(&mut *out, &line)?;
+        return writeln!(out);
+    }
+
+    write!(
+        out,
+        "{},{:.6},{},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6}",
+        step,
+        time,
+        diagnostics.particle_count,
+        diagnostics.kinetic_energy,
+        diagnostics.momentum.x,
+        diagnostics.momentum.y,
+        diagnostics.mean_speed,
+        diagnostics.centre_of_mass.x,
+        diagnostics.centre_of_mass.y,
+        diagnostics.temperature
+    )?;
+    for species in &diagnostics.species {
+        write!(out, ",{},{:.6},{:.6}", species.count, species.mean_speed, species.speed_std_dev)?;
+    }
+    writeln!(out)
+}
+
+// One cluster in a line of `--clusters` output, without its member list
+#[derive(Serialize)]
+struct ClusterSummary<'a> {
+    id: u64,
+    size: usize,
+    species_counts: &'a [usize],
+    centroid: Vec2,
+    radius: f32,
+    velocity: Vec2,
+    angular_momentum: f32,
+    age: u64,
+}
+
+// One line of `--clusters` output
+#[derive(Serialize)]
+struct ClustersLine<'a> {
+    step: u64,
+    time: f64,
+    clusters: Vec<ClusterSummary<'a>>,
+    events: Vec<ClusterEvent>,
+}
+
+fn write_clusters(
+    out: &mut dyn Write,
+    world: &World,
+    options: &Options,
+    tracker: &mut ClusterTracker,
+    step: u64,
+) -> io::Result<()> {
+    let mut clusters = find_clusters(world, &options.cluster_params);
+    let events = tracker.update(&mut clusters);
+    let line = ClustersLine {
+        step,
+        time: step as f64 * options.dt as f64,
+        clusters: clusters
+            .iter()
+            .map(|cluster| ClusterSummary {
+                id: cluster.id,
+                size: cluster.size(),
+                species_counts: &cluster.species_counts,
+                centroid: cluster.centroid,
+                radius: cluster.radius,
+                velocity: cluster.velocity,
+                angular_momentum: cluster.angular_momentum,
+                age: tracker.age(cluster.id).unwrap_or(0),
+            })
+            .collect(),
+        events,
+    };
+    serde_json::to_writer(&mut *out, &line)?;
+    writeln!(out)
+}
+
+// One line of `--rdf` output: g(r) averaged over the samples up to `step`
+#[derive(Serialize)]
+struct RdfLine {
+    step: u64,
+    time: f64,
+    #[serde(flatten)]
+    rdf: Rdf,
+}
+
+fn write_rdf(out: &mut dyn Write, rdf: &mut RadialDistribution, options: &Options, step: u64) -> io::Result<()> {
+    let line = RdfLine {
+        step,
+        time: step as f64 * options.dt as f64,
+        rdf: rdf.result(),
+    };
+    rdf.reset();
+    serde_json::to_writer(&mut *out, &line)?;
+    writeln!(out)
+}
+
+// Buffered writer for an output flag's value: a file path, or - for stdout
+fn open_output(path: Option<&str>) -> io::Result<Option<Box<dyn Write>>> {
+    Ok(match path {
+        Some("-") => Some(Box::new(BufWriter::new(io::stdout()))),
+        Some(path) => Some(Box::new(BufWriter::new(File::create(path)?))),
+        None => None,
+    })
+}
+
+fn write_snapshot(world: &World, options: &Options, step: u64) -> Result<(), Box<dyn Error>> {
+    let path = options
+        .output_dir
+        .join(format!("step_{:08}.{}", step, options.snapshot_extension));
+    world.save_snapshot(&path)?;
+    eprintln!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn run_comparison(world: &World, options: &Options) -> Result<(), Box<dyn Error>> {
+    let matrix = world.get_interaction_matrix();
+    let count = matrix.species_count();
+    let symmetric = (0..count).all(|i| (0..count).all(|j| matrix.get(i, j) == matrix.get(j, i)));
+    if !symmetric {
+        eprintln!("warning: the interaction matrix isn't symmetric, so energy isn't conserved even with exact integration");
+    }
+
+    let started = Instant::now();
+    println!("integrator,steps,initial_energy,final_energy,relative_drift,max_deviation");
+    for drift in compare_integrators(&world.snapshot(), options.dt, options.steps)? {
+        println!(
+            "{},{},{:.6},{:.6},{:.6e},{:.6}",
+            drift.integrator.name(),
+            drift.steps,
+            drift.initial_energy,
+            drift.final_energy,
+            drift.relative_drift(),
+            drift.max_deviation
+        );
+    }
+    eprintln!("Compared integrators in {:.2} s", started.elapsed().as_secs_f64());
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let mut world = build_world(&options)?;
+    let friction = match world.get_physics().friction_half_life {
+        Some(half_life) => format!("{} s", half_life),
+        None => "none".to_string(),
+    };
+    eprintln!(
+        "Seed {}, {} particles, {} index, {} boundary, {} kernel, {} integrator, friction half-life {}, {} steps of {} s",
+        world.seed(),
+        world.particle_count(),
+        world.get_physics().spatial_index.name(),
+        world.get_physics().boundary.name(),
+        world.get_physics().kernel.name(),
+        world.get_physics().integrator.name(),
+        friction,
+        options.steps,
+        options.dt
+    );
+
+    if options.compare_integrators {
+        return run_comparison(&world, &options);
+    }
+
+    if options.write_snapshots {
+        std::fs::create_dir_all(&options.output_dir)?;
+    }
+
+    let mut stats = open_output(options.stats.as_deref())?;
+    if let Some(out) = stats.as_mut() {
+        write_stats_header(out, &world, options.stats_format)?;
+        write_stats_row(out, &world, options.stats_format, 0, 0.0)?;
+    }
+
+    let mut clusters = open_output(options.clusters.as_deref())?
+        .map(|out| (out, ClusterTracker::new(options.cluster_params.min_size)));
+    if let Some((out, tracker)) = clusters.as_mut() {
+        write_clusters(out, &world, &options, tracker, 0)?;
+    }
+
+    let mut rdf = open_output(options.rdf.as_deref())?
+        .map(|out| (out, RadialDistribution::new(options.rdf_params, world.get_species().len())));
+
+    let started = Instant::now();
+    for step in 1..=options.steps {
+        world.update(options.dt);
+
+        if let Some(out) = stats.as_mut() {
+            if step.is_multiple_of(options.stats_every) {
+                write_stats_row(out, &world, options.stats_format, step, step as f64 * options.dt as f64)?;
+            }
+        }
+        if let Some((out, tracker)) = clusters.as_mut() {
+            if step.is_multiple_of(options.clusters_every) {
+                write_clusters(out, &world, &options, tracker, step)?;
+            }
+        }
+        if let Some((out, rdf)) = rdf.as_mut() {
+            if step.is_multiple_of(options.rdf_every) {
+                rdf.add_sample(&world);
+                if rdf.samples() >= options.rdf_window {
+                    write_rdf(out, rdf, &options, step)?;
+                }
+            }
+        }
+        if options.write_snapshots && options.snapshot_every > 0 && step.is_multiple_of(options.snapshot_every) {
+            write_snapshot(&world, &options, step)?;
+        }
+    }
+
+    if let Some(out) = stats.as_mut() {
+        out.flush()?;
+    }
+    if let Some((out, _)) = clusters.as_mut() {
+        out.flush()?;
+    }
+    if let Some((out, rdf)) = rdf.as_mut() {
+        // Whatever is left of the last window
+        if rdf.samples() > 0 {
+            write_rdf(out, rdf, &options, options.steps)?;
+        }
+        out.flush()?;
+    }
+    // Always keep the final state unless it was just written or snapshots are off
+    if options.write_snapshots && (options.snapshot_every == 0 || !options.steps.is_multiple_of(options.snapshot_every)) {
+        write_snapshot(&world, &options, options.steps)?;
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    eprintln!(
+        "Ran {} steps in {:.2} s ({:.1} steps/s)",
+        options.steps,
+        elapsed,
+        options.steps as f64 / elapsed.max(f64::EPSILON)
+    );
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(options) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,7 @@
+//! Particle life simulation core: particles, species, the interaction
+//! matrix and the `World` that steps them, plus tools for analysing runs.
+
+pub mod analysis;
+pub mod particle;
+pub mod simulation;
+pub mod utils;
@@ -1,24 +1,36 @@
 use ggez::{
     event::{self, EventHandler},
     graphics::{Canvas, Color, DrawParam, Text},
-    input::keyboard::{KeyInput, KeyCode},
+    input::keyboard::{KeyInput, KeyCode, KeyMods},
     input::mouse::MouseButton,
     Context, GameResult,
 };
 use glam::Vec2;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::time::Instant;
 
-mod particle;
-mod simulation;
-mod utils;
+use particle_life_simulator::particle::species::{BLUE, GREEN, NEON_PINK, RED};
+use particle_life_simulator::particle::{SpeciesId, SpeciesRegistry};
+use particle_life_simulator::simulation::recording::{Player, RecordedEvent, Recorder, Recording};
+use particle_life_simulator::simulation::timestep::{MAX_SUBSTEPS, TIME_SCALES};
+use particle_life_simulator::simulation::{
+    BoundaryMode, Command, FixedTimestep, History, InteractionChange, InteractionMatrix, Integrator, Kernel,
+    MatrixDistribution, PhysicsParams, Preset, SpatialIndexKind, World,
+};
+use particle_life_simulator::utils::math::Vec2 as MyVec2;
 
-use particle::{Particle, ParticleType};
-use simulation::World;
-use utils::math::Vec2 as MyVec2;
+mod matrix_editor;
+use matrix_editor::MatrixEditor;
 
 const WINDOW_WIDTH: f32 = 1200.0;
 const WINDOW_HEIGHT: f32 = 800.0;
+const SNAPSHOT_PATH: &str = "snapshot.json";
+const RECORDING_PATH: &str = "recording.json";
+const PRESETS_DIR: &str = "presets";
+const MATRIX_TEXT_PATH: &str = "matrix.txt"; // Where the matrix editor exports to and imports from
+const SEEK_FRAMES: usize = 60; // Frames skipped per arrow key press during playback
+const DELETE_RADIUS: f32 = 20.0; // Particles this close to the cursor are deleted
 
 struct ParticleLifeGame {
     world: World,
@@ -28,18 +40,33 @@ struct ParticleLifeGame {
     frame_count: u32,
     current_fps: u32,
     cursor_pos: Vec2,
-    selected_param: Option<usize>, // 0: red_red, 1: red_blue, 2: blue_red, 3: blue_blue
+    matrix_editor: MatrixEditor,
+    history: History, // Undo/redo of edits made through the window
+    drag_edited: bool, // Whether the current matrix editor drag has changed a cell yet
+    recorder: Option<Recorder>,
+    player: Option<Player>,
+    timestep: FixedTimestep,
+    previous_positions: Vec<MyVec2>, // Positions before the last step, for interpolated drawing
+    presets: Vec<Preset>, // Bound to keys 1-9 in order
+    species_override: Option<SpeciesRegistry>, // From --species, replaces each preset's species
+    rules_distribution: MatrixDistribution, // Used by the randomize rules key
+    rules_seed: Option<u64>, // Seed of the current random matrix, None for a preset's own
 }
 
 impl ParticleLifeGame {
-    fn new(_ctx: &mut Context) -> GameResult<Self> {
-        let mut world = World::new(WINDOW_WIDTH, WINDOW_HEIGHT);
-        
-        // Load preset 1 automatically on application start
-        world.load_preset(1);
-        println!("Loaded preset 1 on application start");
+    fn new(
+        _ctx: &mut Context,
+        species: Option<SpeciesRegistry>,
+        seed: Option<u64>,
+        rules: Option<(MatrixDistribution, Option<u64>)>,
+    ) -> GameResult<Self> {
+        let world = match seed {
+            Some(seed) => World::new_with_seed(WINDOW_WIDTH, WINDOW_HEIGHT, seed),
+            None => World::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+        };
+        println!("World seed: {} (rerun with --seed {} to reproduce)", world.seed(), world.seed());
         
-        Ok(Self {
+        let mut game = Self {
             world,
             paused: false,
             show_debug: false,
@@ -47,44 +74,453 @@ impl ParticleLifeGame {
             frame_count: 0,
             current_fps: 0,
             cursor_pos: Vec2::ZERO,
-            selected_param: None,
-        })
+            matrix_editor: MatrixEditor::new(WINDOW_WIDTH),
+            history: History::default(),
+            drag_edited: false,
+            recorder: None,
+            player: None,
+            timestep: FixedTimestep::default(),
+            previous_positions: Vec::new(),
+            presets: load_presets(),
+            species_override: species,
+            rules_distribution: MatrixDistribution::default(),
+            rules_seed: None,
+        };
+        
+        // Load preset 1 automatically on application start
+        game.load_preset(1);
+        if let Some((distribution, seed)) = rules {
+            game.rules_distribution = distribution;
+            game.randomize_rules(seed);
+        }
+        game.history.clear(); // The starting state is not undoable
+        Ok(game)
     }
     
-    fn add_particle_at_cursor(&mut self, particle_type: ParticleType) {
-        let mut rng = rand::thread_rng();
+    fn add_particle_at_cursor(&mut self, species: SpeciesId) {
+        // Worlds loaded from presets or snapshots may have fewer species
+        // than there are mouse buttons
+        if self.player.is_some() || self.world.get_species().get(species).is_none() {
+            return;
+        }
+        let rng = self.world.rng_mut();
         let velocity = MyVec2::new(
             rng.gen_range(-50.0..50.0),
             rng.gen_range(-50.0..50.0),
         );
         
-        self.world.add_particle(Particle::new(
+        self.world.spawn_particle(
             MyVec2::new(self.cursor_pos.x, self.cursor_pos.y),
             velocity,
-            particle_type,
+            species,
             1.0,
             3.0,
-        ));
+        );
+        
+        let Some(particle) = self.world.get_particles().last().cloned() else {
+            return;
+        };
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(RecordedEvent::SpawnParticle(particle.clone()));
+        }
+        self.history.push(Command::SpawnParticles {
+            first: self.world.particle_count() - 1,
+            particles: vec![particle],
+        });
+    }
+    
+    fn delete_particles_at_cursor(&mut self) {
+        if self.player.is_some() {
+            return;
+        }
+        let cursor = MyVec2::new(self.cursor_pos.x, self.cursor_pos.y);
+        let indices: Vec<usize> = self
+            .world
+            .get_particles()
+            .iter()
+            .enumerate()
+            .filter(|(_, particle)| self.world.displacement(cursor, particle.position).length() <= DELETE_RADIUS)
+            .map(|(index, _)| index)
+            .collect();
+        if indices.is_empty() {
+            return;
+        }
+        // Back to front, so earlier indices stay valid
+        let mut removed: Vec<(usize, _)> = indices
+            .iter()
+            .rev()
+            .map(|&index| (index, self.world.remove_particle(index)))
+            .collect();
+        removed.reverse();
+        self.previous_positions.clear();
+        println!("Deleted {} particles", removed.len());
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(RecordedEvent::RemoveParticles(indices));
+        }
+        self.history.push(Command::RemoveParticles(removed));
+    }
+    
+    fn load_preset(&mut self, preset: u32) {
+        let Some(definition) = preset.checked_sub(1).and_then(|index| self.presets.get(index as usize)) else {
+            println!("No preset {}", preset);
+            return;
+        };
+        let before = self.world.snapshot();
+        if let Err(e) = self.world.apply_preset(definition) {
+            eprintln!("Failed to load preset {}: {}", preset, e);
+            return;
+        }
+        println!("Loaded preset {}: {}", preset, definition.name);
+        if let Some(species) = &self.species_override {
+            self.world.set_species(species.clone());
+        }
+        self.rules_seed = None;
+        self.matrix_editor.clear_selection();
+        self.previous_positions.clear();
+        let after = Box::new(self.world.snapshot());
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(RecordedEvent::PresetLoaded {
+                preset,
+                state: after.clone(),
+            });
+        }
+        self.history.push(Command::ReplaceState {
+            before: Box::new(before),
+            after,
+        });
+    }
+    
+    fn load_snapshot(&mut self) {
+        let before = self.world.snapshot();
+        if let Err(e) = self.world.load_snapshot(SNAPSHOT_PATH) {
+            eprintln!("Failed to load snapshot: {}", e);
+            return;
+        }
+        println!("Loaded snapshot from {}", SNAPSHOT_PATH);
+        self.matrix_editor.clear_selection();
+        self.previous_positions.clear();
+        let after = Box::new(self.world.snapshot());
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(RecordedEvent::SnapshotLoaded(after.clone()));
+        }
+        self.history.push(Command::ReplaceState {
+            before: Box::new(before),
+            after,
+        });
+    }
+    
+    // Undoes the latest edit, or redoes the latest undone one
+    fn step_history(&mut self, redo: bool) {
+        if self.player.is_some() {
+            return;
+        }
+        let result = if redo {
+            self.history.redo(&mut self.world)
+        } else {
+            self.history.undo(&mut self.world)
+        };
+        let (verb, done) = if redo { ("redo", "Redid") } else { ("undo", "Undid") };
+        let command = match result {
+            Ok(Some(command)) => command,
+            Ok(None) => {
+                println!("Nothing to {}", verb);
+                return;
+            }
+            Err(e) => {
+                eprintln!("Failed to {}: {}", verb, e);
+                return;
+            }
+        };
+        println!("{} {}", done, command.describe());
+        if matches!(command, Command::SetMatrix { .. } | Command::ReplaceState { .. }) {
+            self.rules_seed = None;
+            self.matrix_editor.clear_selection();
+        }
+        self.previous_positions.clear();
+        // The world may have changed in ways no other event describes
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(RecordedEvent::SnapshotLoaded(Box::new(self.world.snapshot())));
+        }
+    }
+    
+    fn toggle_recording(&mut self) {
+        match self.recorder.take() {
+            Some(recorder) => {
+                let recording = recorder.finish();
+                match recording.save(RECORDING_PATH) {
+                    Ok(()) => println!(
+                        "Saved recording of {} frames to {}",
+                        recording.frame_count(),
+                        RECORDING_PATH
+                    ),
+                    Err(e) => eprintln!("Failed to save recording: {}", e),
+                }
+            }
+            None => {
+                self.recorder = Some(Recorder::new(&self.world));
+                println!("Recording started");
+            }
+        }
+    }
+    
+    fn toggle_playback(&mut self) {
+        if self.player.take().is_some() {
+            println!("Playback stopped");
+            return;
+        }
+        match Recording::load(RECORDING_PATH).and_then(Player::new) {
+            Ok(player) => {
+                println!("Playing {} frames from {}", player.frame_count(), RECORDING_PATH);
+                self.previous_positions.clear();
+                self.player = Some(player);
+            }
+            Err(e) => eprintln!("Failed to load recording: {}", e),
+        }
+    }
+    
+    fn seek_playback(&mut self, forward: bool) {
+        if let Some(player) = &mut self.player {
+            let frame = if forward {
+                player.frame() + SEEK_FRAMES
+            } else {
+                player.frame().saturating_sub(SEEK_FRAMES)
+            };
+            self.previous_positions.clear();
+            match player.seek(frame) {
+                Ok(()) => println!("Playback at frame {}/{}", player.frame(), player.frame_count()),
+                Err(e) => eprintln!("Failed to seek recording: {}", e),
+            }
+        }
+    }
+    
+    // The world being shown: the playback world while replaying a recording
+    fn displayed_world(&self) -> &World {
+        match &self.player {
+            Some(player) => player.world(),
+            None => &self.world,
+        }
     }
     
     fn adjust_interaction_param(&mut self, delta: f32) {
-        if let Some(param) = self.selected_param {
-            let matrix = self.world.get_interaction_matrix_mut();
-            match param {
-                0 => matrix.red_red += delta,
-                1 => matrix.red_blue += delta,
-                2 => matrix.red_green += delta,
-                3 => matrix.blue_red += delta,
-                4 => matrix.blue_blue += delta,
-                5 => matrix.blue_green += delta,
-                6 => matrix.green_red += delta,
-                7 => matrix.green_blue += delta,
-                8 => matrix.green_green += delta,
-                _ => {}
-            }
-            println!("Adjusted interaction parameter {} by {:.2}", param, delta);
+        if self.player.is_some() {
+            return;
+        }
+        let edits = self
+            .matrix_editor
+            .adjust_selected(delta, self.world.get_interaction_matrix_mut());
+        for edit in &edits {
+            println!(
+                "Adjusted {} interaction by {:.2} to {:.2}",
+                self.pair_name(edit.source, edit.target),
+                delta,
+                edit.after
+            );
+        }
+        self.record_interaction_edits(edits, false);
+    }
+    
+    // Records matrix editor changes, folding them into the previous history
+    // entry when `merge` is set (later moves of the same drag)
+    fn record_interaction_edits(&mut self, edits: Vec<InteractionChange>, merge: bool) {
+        if edits.is_empty() {
+            return;
+        }
+        if let Some(recorder) = &mut self.recorder {
+            for edit in &edits {
+                recorder.record_event(RecordedEvent::SetInteraction {
+                    source: edit.source,
+                    target: edit.target,
+                    value: edit.after,
+                });
+            }
+        }
+        if merge {
+            self.history.push_or_merge(Command::SetInteractions(edits));
+        } else {
+            self.history.push(Command::SetInteractions(edits));
+        }
+    }
+    
+    fn record_matrix_change(&mut self, before: InteractionMatrix) {
+        let after = self.world.get_interaction_matrix().clone();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(RecordedEvent::SetMatrix(after.clone()));
+        }
+        self.history.push(Command::SetMatrix { before, after });
+    }
+    
+    // Prints the strengths and writes them to MATRIX_TEXT_PATH
+    fn export_matrix(&self) {
+        let text = self.displayed_world().get_interaction_matrix().to_text();
+        print!("Interaction matrix:\n{}", text);
+        match std::fs::write(MATRIX_TEXT_PATH, &text) {
+            Ok(()) => println!("Exported interaction matrix to {}", MATRIX_TEXT_PATH),
+            Err(e) => eprintln!("Failed to write {}: {}", MATRIX_TEXT_PATH, e),
+        }
+    }
+    
+    // Replaces the strengths with those in MATRIX_TEXT_PATH, keeping the radii
+    fn import_matrix(&mut self) {
+        if self.player.is_some() {
+            return;
+        }
+        let imported = std::fs::read_to_string(MATRIX_TEXT_PATH)
+            .map_err(|e| e.to_string())
+            .and_then(|text| InteractionMatrix::from_text(&text));
+        let imported = match imported {
+            Ok(imported) => imported,
+            Err(e) => {
+                eprintln!("Failed to import interaction matrix from {}: {}", MATRIX_TEXT_PATH, e);
+                return;
+            }
+        };
+        let before = self.world.get_interaction_matrix().clone();
+        let matrix = self.world.get_interaction_matrix_mut();
+        let n = matrix.species_count();
+        if imported.species_count() != n {
+            eprintln!(
+                "Imported interaction matrix covers {} species, the world has {}",
+                imported.species_count(),
+                n
+            );
+            return;
+        }
+        for source in 0..n {
+            for target in 0..n {
+                matrix.set(source, target, imported.get(source, target));
+            }
+        }
+        self.rules_seed = None;
+        self.record_matrix_change(before);
+        println!("Imported interaction matrix from {}", MATRIX_TEXT_PATH);
+    }
+    
+    // Redraws the interaction strengths, keeping the particles. The seed is
+    // drawn from the world's RNG unless given, and printed so the rules can
+    // be reproduced.
+    fn randomize_rules(&mut self, seed: Option<u64>) {
+        let seed = seed.unwrap_or_else(|| self.world.rng_mut().gen());
+        let distribution = self.rules_distribution;
+        let before = self.world.get_interaction_matrix().clone();
+        self.world
+            .get_interaction_matrix_mut()
+            .randomize(&mut StdRng::seed_from_u64(seed), distribution);
+        self.rules_seed = Some(seed);
+        self.record_matrix_change(before);
+        println!(
+            "Randomized rules: {} with seed {} (rerun with --random-matrix {} --matrix-seed {} to reproduce)",
+            distribution.name(),
+            seed,
+            distribution.name(),
+            seed
+        );
+    }
+    
+    fn cycle_rules_distribution(&mut self) {
+        self.rules_distribution = next_in(&MatrixDistribution::ALL, self.rules_distribution);
+        self.randomize_rules(None);
+    }
+    
+    fn cycle_spatial_index(&mut self) {
+        let before = *self.world.get_physics();
+        let physics = self.world.get_physics_mut();
+        physics.spatial_index = next_in(&SpatialIndexKind::ALL, physics.spatial_index);
+        self.record_physics_change(before);
+        println!("Spatial index: {}", self.world.get_physics().spatial_index.name());
+    }
+    
+    fn cycle_boundary_mode(&mut self) {
+        let before = *self.world.get_physics();
+        let physics = self.world.get_physics_mut();
+        physics.boundary = next_in(&BoundaryMode::ALL, physics.boundary);
+        self.record_physics_change(before);
+        println!("Boundary: {}", self.world.get_physics().boundary.name());
+    }
+    
+    fn cycle_kernel(&mut self) {
+        // Switching kernels starts the new one from its default parameters
+        let before = *self.world.get_physics();
+        let physics = self.world.get_physics_mut();
+        let kernels = Kernel::all();
+        let next = kernels.iter().position(|kernel| kernel.name() == physics.kernel.name()).map_or(0, |i| i + 1);
+        physics.kernel = kernels[next % kernels.len()];
+        self.record_physics_change(before);
+        println!("Force kernel: {}", self.world.get_physics().kernel.name());
+    }
+    
+    fn cycle_integrator(&mut self) {
+        let before = *self.world.get_physics();
+        let physics = self.world.get_physics_mut();
+        physics.integrator = next_in(&Integrator::ALL, physics.integrator);
+        self.record_physics_change(before);
+        println!("Integrator: {}", self.world.get_physics().integrator.name());
+    }
+    
+    // Advances the displayed world by one fixed step
+    fn step_simulation(&mut self) {
+        self.previous_positions = self
+            .displayed_world()
+            .get_particles()
+            .iter()
+            .map(|particle| particle.position)
+            .collect();
+        
+        if let Some(player) = &mut self.player {
+            if let Err(e) = player.step() {
+                eprintln!("Playback failed: {}", e);
+                self.player = None;
+            }
+        } else {
+            let dt = self.timestep.step_dt();
+            let count = self.world.particle_count();
+            self.world.update(dt);
+            // Absorbed particles shift the indices spawns and deletions refer to
+            if self.world.particle_count() < count {
+                let dropped = self.history.forget_particle_edits();
+                if dropped > 0 {
+                    println!("Particles were absorbed: dropped {} spawns and deletions from the undo history", dropped);
+                }
+            }
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record_step(&self.world, dt);
+            }
         }
     }
+    
+    fn change_time_scale(&mut self, faster: bool) {
+        let current = TIME_SCALES.iter().position(|&scale| scale == self.timestep.time_scale()).unwrap_or(2);
+        let next = if faster {
+            (current + 1).min(TIME_SCALES.len() - 1)
+        } else {
+            current.saturating_sub(1)
+        };
+        self.timestep.set_time_scale(TIME_SCALES[next]);
+        println!("Time scale: {}x", TIME_SCALES[next]);
+    }
+    
+    fn change_substeps(&mut self, more: bool) {
+        let substeps = self.timestep.substeps();
+        let substeps = if more { (substeps + 1).min(MAX_SUBSTEPS) } else { substeps.saturating_sub(1).max(1) };
+        self.timestep.set_substeps(substeps);
+        println!("Substeps per frame: {} ({:.4} s per step)", substeps, self.timestep.step_dt());
+    }
+    
+    fn record_physics_change(&mut self, before: PhysicsParams) {
+        let after = *self.world.get_physics();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(RecordedEvent::SetPhysics(after));
+        }
+        self.history.push(Command::SetPhysics { before, after });
+    }
+    
+    fn pair_name(&self, source: usize, target: usize) -> String {
+        let species = self.world.get_species();
+        format!(
+            "{}-{}",
+            species.name(source as SpeciesId),
+            species.name(target as SpeciesId)
+        )
+    }
 }
 
 impl EventHandler for ParticleLifeGame {
@@ -100,12 +536,12 @@ impl EventHandler for ParticleLifeGame {
             self.fps_timer = Instant::now();
         }
         
-        // Update simulation
+        // Run however many fixed steps the elapsed time covers
         if !self.paused {
-            let dt = ctx.time.delta().as_secs_f32();
-            // Cap delta time to prevent large jumps
-            let dt = dt.min(1.0 / 30.0);
-            self.world.update(dt);
+            let steps = self.timestep.advance(ctx.time.delta().as_secs_f32());
+            for _ in 0..steps {
+                self.step_simulation();
+            }
         }
         
         Ok(())
@@ -115,25 +551,43 @@ impl EventHandler for ParticleLifeGame {
         let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
         
         // Draw particles
-        let particles = self.world.get_particles();
-        for particle in particles {
-            let color = match particle.particle_type {
-                ParticleType::Red => Color::RED,
-                ParticleType::Blue => Color::BLUE,
-                ParticleType::Green => Color::GREEN,
-                ParticleType::NeonPink => Color::new(1.0, 0.0, 0.5, 1.0), // Neon pink color
+        let world = self.displayed_world();
+        let particles = world.get_particles();
+        let species = world.get_species();
+        let wraps = world.get_physics().boundary == BoundaryMode::Wrap;
+        // Draw between the last two steps, by how far time has run into the next
+        let alpha = if self.paused { 1.0 } else { self.timestep.alpha() };
+        let interpolate = self.previous_positions.len() == particles.len();
+        for (i, particle) in particles.iter().enumerate() {
+            let position = if interpolate {
+                let previous = self.previous_positions[i];
+                previous + world.displacement(previous, particle.position) * alpha
+            } else {
+                particle.position
             };
+
+            let color = species
+                .get(particle.species)
+                .map(|s| Color::from(s.color))
+                .unwrap_or(Color::WHITE);
             
             let circle = ggez::graphics::Mesh::new_circle(
                 ctx,
                 ggez::graphics::DrawMode::fill(),
-                Vec2::new(particle.position.x, particle.position.y),
+                Vec2::new(position.x, position.y),
                 particle.radius,
                 0.1,
                 color,
             )?;
             
-            canvas.draw(&circle, DrawParam::default());
+            if wraps {
+                let offsets = wrapped_image_offsets(position, particle.radius, world.width(), world.height());
+                for offset in offsets {
+                    canvas.draw(&circle, DrawParam::default().dest(offset));
+                }
+            } else {
+                canvas.draw(&circle, DrawParam::default());
+            }
         }
         
         // Draw debug info
@@ -142,65 +596,94 @@ impl EventHandler for ParticleLifeGame {
             let debug_bg = ggez::graphics::Mesh::new_rectangle(
                 ctx,
                 ggez::graphics::DrawMode::fill(),
-                ggez::graphics::Rect::new(5.0, 5.0, 300.0, 300.0),
+                ggez::graphics::Rect::new(5.0, 5.0, 300.0, 480.0),
                 Color::new(0.0, 0.0, 0.0, 0.8),
             )?;
             canvas.draw(&debug_bg, DrawParam::default());
 
-            let matrix = self.world.get_interaction_matrix();
-            let debug_text = format!(
-                "FPS: {}\nParticles: {}\nStatus: {}\nCursor: ({:.1}, {:.1})\n\
-                 Interaction Matrix:\n\
-                 Red-Red: {:.2}\nRed-Blue: {:.2}\nRed-Green: {:.2}\nRed-Pink: {:.2}\n\
-                 Blue-Red: {:.2}\nBlue-Blue: {:.2}\nBlue-Green: {:.2}\nBlue-Pink: {:.2}\n\
-                 Green-Red: {:.2}\nGreen-Blue: {:.2}\nGreen-Green: {:.2}\nGreen-Pink: {:.2}\n\
-                 Pink-Red: {:.2}\nPink-Blue: {:.2}\nPink-Green: {:.2}\nPink-Pink: {:.2}\n\
-                 Selected Param: {}",
+            let world = self.displayed_world();
+            let matrix = world.get_interaction_matrix();
+            let session = match (&self.recorder, &self.player) {
+                (_, Some(player)) => format!("PLAYBACK {}/{}", player.frame(), player.frame_count()),
+                (Some(recorder), None) => format!("RECORDING {}", recorder.frame_count()),
+                (None, None) => "-".to_string(),
+            };
+            let rules = match self.rules_seed {
+                Some(seed) => format!("{} (seed {})", self.rules_distribution.name(), seed),
+                None => "preset".to_string(),
+            };
+            let diagnostics = world.diagnostics();
+            let counts: Vec<String> = diagnostics.species.iter().map(|species| species.count.to_string()).collect();
+            let mut debug_text = format!(
+                "FPS: {}\nParticles: {}\nStatus: {}\nIndex: {}\nBoundary: {}\nKernel: {}\nIntegrator: {}\nTime: {}x, {} substeps\nSeed: {}\nRules: {}\nSession: {}\nHistory: {} undo, {} redo ({:.1} MiB)\nCursor: ({:.1}, {:.1})\n\
+                 Energy: {:.1}  Temperature: {:.2}\nMomentum: ({:.1}, {:.1})\nMean Speed: {:.2}\n\
+                 Centre of Mass: ({:.0}, {:.0})\nSpecies Counts: {}\n\
+                 Interaction Matrix:\n",
                 self.current_fps, 
-                self.world.particle_count(), 
+                world.particle_count(), 
                 if self.paused { "PAUSED" } else { "RUNNING" },
+                world.get_physics().spatial_index.name(),
+                world.get_physics().boundary.name(),
+                world.get_physics().kernel.name(),
+                world.get_physics().integrator.name(),
+                self.timestep.time_scale(),
+                self.timestep.substeps(),
+                world.seed(),
+                rules,
+                session,
+                self.history.undo_len(),
+                self.history.redo_len(),
+                self.history.memory_used() as f32 / (1024.0 * 1024.0),
                 self.cursor_pos.x, 
                 self.cursor_pos.y,
-                matrix.red_red,
-                matrix.red_blue,
-                matrix.red_green,
-                matrix.red_pink,
-                matrix.blue_red,
-                matrix.blue_blue,
-                matrix.blue_green,
-                matrix.blue_pink,
-                matrix.green_red,
-                matrix.green_blue,
-                matrix.green_green,
-                matrix.green_pink,
-                matrix.pink_red,
-                matrix.pink_blue,
-                matrix.pink_green,
-                matrix.pink_pink,
-                match self.selected_param {
-                    Some(0) => "Red-Red",
-                    Some(1) => "Red-Blue",
-                    Some(2) => "Red-Green",
-                    Some(3) => "Blue-Red",
-                    Some(4) => "Blue-Blue",
-                    Some(5) => "Blue-Green",
-                    Some(6) => "Green-Red",
-                    Some(7) => "Green-Blue",
-                    Some(8) => "Green-Green",
-                    Some(_) => "Invalid",
-                    None => "None",
-                }
+                diagnostics.kinetic_energy,
+                diagnostics.temperature,
+                diagnostics.momentum.x,
+                diagnostics.momentum.y,
+                diagnostics.mean_speed,
+                diagnostics.centre_of_mass.x,
+                diagnostics.centre_of_mass.y,
+                counts.join("/"),
             );
+            for source in 0..matrix.species_count() {
+                for target in 0..matrix.species_count() {
+                    debug_text.push_str(&format!(
+                        "{}: {:.2}",
+                        self.pair_name(source, target),
+                        matrix.get(source, target)
+                    ));
+                    let min_radius = matrix.min_radius(source, target);
+                    if let Some(max_radius) = matrix.max_radius(source, target) {
+                        debug_text.push_str(&format!(" [{:.0}-{:.0}]", min_radius, max_radius));
+                    } else if min_radius > 0.0 {
+                        debug_text.push_str(&format!(" [{:.0}-]", min_radius));
+                    }
+                    debug_text.push('\n');
+                }
+            }
+            debug_text.push_str(&format!(
+                "Selected Param: {}",
+                match self.matrix_editor.selected() {
+                    Some((source, target)) => self.pair_name(source, target),
+                    None => "None".to_string(),
+                }
+            ));
             
             let text = Text::new(debug_text);
             canvas.draw(&text, DrawParam::default().dest(Vec2::new(10.0, 10.0)).color(Color::WHITE));
         }
         
+        if self.matrix_editor.visible {
+            let world = self.displayed_world();
+            self.matrix_editor
+                .draw(ctx, &mut canvas, world.get_interaction_matrix(), world.get_species())?;
+        }
+        
         // Draw controls background
         let controls_bg = ggez::graphics::Mesh::new_rectangle(
             ctx,
             ggez::graphics::DrawMode::fill(),
-            ggez::graphics::Rect::new(5.0, WINDOW_HEIGHT - 270.0, 300.0, 260.0),
+            ggez::graphics::Rect::new(5.0, WINDOW_HEIGHT - 460.0, 340.0, 450.0),
             Color::new(0.0, 0.0, 0.0, 0.8),
         )?;
         canvas.draw(&controls_bg, DrawParam::default());
@@ -210,17 +693,29 @@ impl EventHandler for ParticleLifeGame {
         "SPACE: Pause/Resume\n\
          R: Reset\n\
          D: Toggle Debug\n\
-         1-6: Load Presets\n\
+         I: Switch Spatial Index\n\
+         B: Switch Boundary (Reflect/Wrap/Absorb)\n\
+         K: Switch Force Kernel\n\
+         T: Switch Integrator (Euler/Verlet/RK4)\n\
+         N: Single Step (while paused)\n\
+         X: Randomize Rules (Shift: Next Distribution)\n\
+         ,/.: Slower/Faster  [/]: Substeps\n\
+         S/L: Save/Load Snapshot\n\
+         C: Start/Stop Recording\n\
+         P: Play/Stop Recording (Left/Right: Seek)\n\
+         1-9: Load Presets\n\
              ESC: Exit\n\
              Left Click: Add Red Particles\n\
              Shift + Left Click: Add Neon Pink Particles\n\
              Right Click: Add Blue Particles\n\
              Middle Click: Add Green Particles\n\
-             F1-F9: Select Interaction Param\n\
-             Numpad +/-: Adjust Selected Param\n\
-             =/- Keys: Adjust Selected Param"
+             M: Matrix Editor (Drag/Scroll a Cell to Edit)\n\
+             Y: Symmetric Lock\n\
+             E: Export Matrix to matrix.txt (Shift: Import)\n\
+             Ctrl+Z/Y: Undo/Redo  Del: Delete Near Cursor\n\
+             Numpad +/- or =/-: Adjust Selected Cell"
         );
-        canvas.draw(&controls_text, DrawParam::default().dest(Vec2::new(10.0, WINDOW_HEIGHT - 260.0)).color(Color::WHITE));
+        canvas.draw(&controls_text, DrawParam::default().dest(Vec2::new(10.0, WINDOW_HEIGHT - 450.0)).color(Color::WHITE));
         
         canvas.finish(ctx)?;
         
@@ -234,77 +729,63 @@ impl EventHandler for ParticleLifeGame {
                 println!("Simulation {}", if self.paused { "paused" } else { "resumed" });
             }
             Some(KeyCode::R) => {
-                self.world.clear();
-                self.world.load_preset(1); // Reset loads preset 1
-                println!("Simulation reset to preset 1");
+                self.load_preset(1); // Reset loads preset 1
+            }
+            Some(KeyCode::I) => self.cycle_spatial_index(),
+            Some(KeyCode::B) => self.cycle_boundary_mode(),
+            Some(KeyCode::K) => self.cycle_kernel(),
+            Some(KeyCode::T) => self.cycle_integrator(),
+            Some(KeyCode::N) if self.paused => self.step_simulation(),
+            Some(KeyCode::X) if ctx.keyboard.is_key_pressed(KeyCode::LShift) || ctx.keyboard.is_key_pressed(KeyCode::RShift) => {
+                self.cycle_rules_distribution()
+            }
+            Some(KeyCode::X) => self.randomize_rules(None),
+            Some(KeyCode::E) if ctx.keyboard.is_key_pressed(KeyCode::LShift) || ctx.keyboard.is_key_pressed(KeyCode::RShift) => {
+                self.import_matrix()
             }
+            Some(KeyCode::E) => self.export_matrix(),
+            Some(KeyCode::Comma) => self.change_time_scale(false),
+            Some(KeyCode::Period) => self.change_time_scale(true),
+            Some(KeyCode::LBracket) => self.change_substeps(false),
+            Some(KeyCode::RBracket) => self.change_substeps(true),
             Some(KeyCode::D) => {
                 self.show_debug = !self.show_debug;
                 println!("Debug display {}", if self.show_debug { "enabled" } else { "disabled" });
             }
-            Some(KeyCode::Escape) => {
-                ctx.request_quit();
-            }
-            Some(KeyCode::Key1) => {
-                self.world.load_preset(1);
-                println!("Loaded preset 1");
-            }
-            Some(KeyCode::Key2) => {
-                self.world.load_preset(2);
-                println!("Loaded preset 2");
-            }
-            Some(KeyCode::Key3) => {
-                self.world.load_preset(3);
-                println!("Loaded preset 3");
-            }
-            Some(KeyCode::Key4) => {
-                self.world.load_preset(4);
-                println!("Loaded preset 4");
-            }
-            Some(KeyCode::Key5) => {
-                self.world.load_preset(5);
-                println!("Loaded preset 5");
-            }
-            Some(KeyCode::Key6) => {
-                self.world.load_preset(6);
-                println!("Loaded preset 6");
-            }
-            Some(KeyCode::F1) => {
-                self.selected_param = Some(0);
-                println!("Selected Red-Red interaction");
-            }
-            Some(KeyCode::F2) => {
-                self.selected_param = Some(1);
-                println!("Selected Red-Blue interaction");
-            }
-            Some(KeyCode::F3) => {
-                self.selected_param = Some(2);
-                println!("Selected Blue-Red interaction");
-            }
-            Some(KeyCode::F4) => {
-                self.selected_param = Some(3);
-                println!("Selected Blue-Red interaction");
-            }
-            Some(KeyCode::F5) => {
-                self.selected_param = Some(4);
-                println!("Selected Blue-Blue interaction");
-            }
-            Some(KeyCode::F6) => {
-                self.selected_param = Some(5);
-                println!("Selected Blue-Green interaction");
+            Some(KeyCode::S) => {
+                match self.world.save_snapshot(SNAPSHOT_PATH) {
+                    Ok(()) => println!("Saved snapshot to {}", SNAPSHOT_PATH),
+                    Err(e) => eprintln!("Failed to save snapshot: {}", e),
+                }
             }
-            Some(KeyCode::F7) => {
-                self.selected_param = Some(6);
-                println!("Selected Green-Red interaction");
+            Some(KeyCode::L) => self.load_snapshot(),
+            Some(KeyCode::Z) if input.mods.contains(KeyMods::CTRL) => self.step_history(false),
+            Some(KeyCode::Y) if input.mods.contains(KeyMods::CTRL) => self.step_history(true),
+            Some(KeyCode::Delete) | Some(KeyCode::Back) => self.delete_particles_at_cursor(),
+            Some(KeyCode::C) => self.toggle_recording(),
+            Some(KeyCode::M) => {
+                self.matrix_editor.visible = !self.matrix_editor.visible;
+                println!("Matrix editor {}", if self.matrix_editor.visible { "shown" } else { "hidden" });
             }
-            Some(KeyCode::F8) => {
-                self.selected_param = Some(7);
-                println!("Selected Green-Blue interaction");
+            Some(KeyCode::Y) => {
+                self.matrix_editor.symmetric = !self.matrix_editor.symmetric;
+                println!("Symmetric matrix edits {}", if self.matrix_editor.symmetric { "on" } else { "off" });
             }
-            Some(KeyCode::F9) => {
-                self.selected_param = Some(8);
-                println!("Selected Green-Green interaction");
+            Some(KeyCode::P) => self.toggle_playback(),
+            Some(KeyCode::Left) => self.seek_playback(false),
+            Some(KeyCode::Right) => self.seek_playback(true),
+            Some(KeyCode::Escape) => {
+                ctx.request_quit();
             }
+            Some(KeyCode::Key1) => self.load_preset(1),
+            Some(KeyCode::Key2) => self.load_preset(2),
+            Some(KeyCode::Key3) => self.load_preset(3),
+            Some(KeyCode::Key4) => self.load_preset(4),
+            Some(KeyCode::Key5) => self.load_preset(5),
+            Some(KeyCode::Key6) => self.load_preset(6),
+            Some(KeyCode::Key7) => self.load_preset(7),
+            Some(KeyCode::Key8) => self.load_preset(8),
+            Some(KeyCode::Key9) => self.load_preset(9),
             Some(KeyCode::NumpadAdd) => {
                 self.adjust_interaction_param(0.05);
             }
@@ -327,26 +808,171 @@ impl EventHandler for ParticleLifeGame {
     fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) -> GameResult {
         self.cursor_pos = Vec2::new(x, y);
         
-        let particle_type = match button {
+        // Clicks on the matrix editor edit it rather than spawning particles
+        let species_count = self.world.get_interaction_matrix().species_count();
+        if self.matrix_editor.contains(self.cursor_pos, species_count) {
+            if self.player.is_none() {
+                let edits = self
+                    .matrix_editor
+                    .press(button, self.cursor_pos, self.world.get_interaction_matrix_mut());
+                self.drag_edited = false;
+                self.record_interaction_edits(edits, false);
+            }
+            return Ok(());
+        }
+        
+        let species = match button {
             MouseButton::Left => {
                 if ctx.keyboard.is_key_pressed(KeyCode::LShift) || ctx.keyboard.is_key_pressed(KeyCode::RShift) {
-                    ParticleType::NeonPink
+                    NEON_PINK
                 } else {
-                    ParticleType::Red
+                    RED
                 }
             }
-            MouseButton::Right => ParticleType::Blue,
-            MouseButton::Middle => ParticleType::Green,
+            MouseButton::Right => BLUE,
+            MouseButton::Middle => GREEN,
             _ => return Ok(()),
         };
         
-        self.add_particle_at_cursor(particle_type);
+        self.add_particle_at_cursor(species);
         
         Ok(())
     }
+    
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, _button: MouseButton, _x: f32, _y: f32) -> GameResult {
+        self.matrix_editor.release();
+        Ok(())
+    }
+    
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) -> GameResult {
+        if self.player.is_none() {
+            let edits = self
+                .matrix_editor
+                .drag_to(Vec2::new(x, y), self.world.get_interaction_matrix_mut());
+            if !edits.is_empty() {
+                // The whole drag undoes as one step
+                self.record_interaction_edits(edits, self.drag_edited);
+                self.drag_edited = true;
+            }
+        }
+        Ok(())
+    }
+    
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        let position: Vec2 = ctx.mouse.position().into();
+        let species_count = self.world.get_interaction_matrix().species_count();
+        if self.player.is_none() && self.matrix_editor.contains(position, species_count) {
+            let edits = self
+                .matrix_editor
+                .scroll(position, y, self.world.get_interaction_matrix_mut());
+            self.record_interaction_edits(edits, false);
+        }
+        Ok(())
+    }
+}
+
+// The entry after `current` in `options`, wrapping around
+fn next_in<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let next = options.iter().position(|&option| option == current).map_or(0, |i| i + 1);
+    options[next % options.len()]
+}
+
+// Offsets at which a particle must also be drawn so it shows on both sides
+// of any edge it straddles on a torus
+fn wrapped_image_offsets(position: MyVec2, radius: f32, width: f32, height: f32) -> Vec<Vec2> {
+    let shifts = |value: f32, size: f32| {
+        [
+            Some(0.0),
+            (value - radius < 0.0).then_some(size),
+            (value + radius > size).then_some(-size),
+        ]
+    };
+    let mut offsets = Vec::new();
+    for dx in shifts(position.x, width).into_iter().flatten() {
+        for dy in shifts(position.y, height).into_iter().flatten() {
+            offsets.push(Vec2::new(dx, dy));
+        }
+    }
+    offsets
+}
+
+// Value following `flag` on the command line, e.g. `--seed 42`
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let i = args.iter().position(|arg| arg == flag)?;
+    args.get(i + 1).map(String::as_str)
+}
+
+// Presets from PRESETS_DIR in file name order, or the built-in ones when the
+// directory is missing or has none that load
+fn load_presets() -> Vec<Preset> {
+    let presets: Vec<Preset> = match Preset::scan_dir(PRESETS_DIR) {
+        Ok(found) => found
+            .into_iter()
+            .filter_map(|(path, preset)| match preset {
+                Ok(preset) => Some(preset),
+                Err(e) => {
+                    eprintln!("Skipping preset {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to read presets from {}: {}", PRESETS_DIR, e);
+            Vec::new()
+        }
+    };
+    if presets.is_empty() {
+        println!("Using the built-in presets");
+        return Preset::builtin();
+    }
+    println!("Loaded {} presets from {}", presets.len(), PRESETS_DIR);
+    presets
 }
 
 fn main() -> GameResult {
+    let args: Vec<String> = std::env::args().collect();
+    
+    // Optional RNG seed: --seed <u64>
+    let seed = match arg_value(&args, "--seed").map(str::parse::<u64>) {
+        Some(Ok(seed)) => Some(seed),
+        Some(Err(e)) => {
+            eprintln!("Ignoring invalid --seed: {}", e);
+            None
+        }
+        None => None,
+    };
+    
+    // Optional species definitions: --species <file.json>
+    let species = match args.iter().position(|arg| arg == "--species") {
+        Some(i) => {
+            let path = args.get(i + 1).map(String::as_str).unwrap_or("species.json");
+            match SpeciesRegistry::load(path) {
+                Ok(registry) => {
+                    println!("Loaded {} species from {}", registry.len(), path);
+                    Some(registry)
+                }
+                Err(e) => {
+                    eprintln!("Failed to load species from {}: {}", path, e);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+    
+    // Optional random rules instead of preset 1's: --random-matrix <name> [--matrix-seed <u64>]
+    let rules = match arg_value(&args, "--random-matrix").map(|name| (name, MatrixDistribution::from_name(name))) {
+        Some((_, Some(distribution))) => {
+            let seed = arg_value(&args, "--matrix-seed").and_then(|seed| seed.parse::<u64>().ok());
+            Some((distribution, seed))
+        }
+        Some((name, None)) => {
+            eprintln!("Ignoring unknown --random-matrix {:?}", name);
+            None
+        }
+        None => None,
+    };
+    
     let cb = ggez::ContextBuilder::new("particle-life-game", "Your Name")
         .window_setup(ggez::conf::WindowSetup::default().title("Particle Life Game"))
         .window_mode(ggez::conf::WindowMode::default()
@@ -356,6 +982,6 @@ fn main() -> GameResult {
     
     let (mut ctx, event_loop) = cb.build()?;
     
-    let game = ParticleLifeGame::new(&mut ctx)?;
+    let game = ParticleLifeGame::new(&mut ctx, species, seed, rules)?;
     event::run(ctx, event_loop, game)
 }
\ No newline at end of file
@@ -0,0 +1,255 @@
+//! On-screen editor for the interaction matrix: a grid of colour-coded
+//! cells, one per species pair, edited with the mouse.
+
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, MeshBuilder, Rect, Text, TextLayout};
+use ggez::input::mouse::MouseButton;
+use ggez::{Context, GameResult};
+use glam::Vec2;
+
+use particle_life_simulator::particle::{SpeciesId, SpeciesRegistry};
+use particle_life_simulator::simulation::{InteractionChange, InteractionMatrix};
+
+const MAX_CELL_SIZE: f32 = 40.0;
+const MAX_GRID_SIZE: f32 = 560.0; // Cells shrink to keep large matrices on screen
+const HEADER_SIZE: f32 = 14.0; // Species colour strips along the top and left
+const MARGIN: f32 = 10.0;
+const LABEL_HEIGHT: f32 = 20.0; // Symmetric lock label under the grid
+const DRAG_PER_PIXEL: f32 = 0.01; // Strength change per pixel dragged upwards
+const SCROLL_STEP: f32 = 0.05;
+const LIMIT: f32 = 1.0; // Edits keep strengths in -LIMIT..=LIMIT
+
+pub struct MatrixEditor {
+    pub visible: bool,
+    /// Mirror every edit of `(a, b)` onto `(b, a)`.
+    pub symmetric: bool,
+    selected: Option<(usize, usize)>,
+    drag: Option<Drag>,
+    window_width: f32,
+}
+
+struct Drag {
+    cell: (usize, usize),
+    start_y: f32,
+    start_value: f32,
+}
+
+impl MatrixEditor {
+    pub fn new(window_width: f32) -> Self {
+        Self {
+            visible: false,
+            symmetric: false,
+            selected: None,
+            drag: None,
+            window_width,
+        }
+    }
+
+    pub fn selected(&self) -> Option<(usize, usize)> {
+        self.selected
+    }
+
+    /// Forgets the selection, e.g. when a matrix of another size is loaded.
+    pub fn clear_selection(&mut self) {
+        self.selected = None;
+        self.drag = None;
+    }
+
+    fn cell_size(species_count: usize) -> f32 {
+        MAX_CELL_SIZE.min(MAX_GRID_SIZE / species_count as f32)
+    }
+
+    // Top left corner of the cell grid, right of the row headers and under
+    // the column headers; the panel sits in the top right of the window
+    fn grid_origin(&self, species_count: usize) -> Vec2 {
+        let grid = Self::cell_size(species_count) * species_count as f32;
+        Vec2::new(self.window_width - MARGIN - grid, MARGIN + HEADER_SIZE)
+    }
+
+    fn panel(&self, species_count: usize) -> Rect {
+        let grid = Self::cell_size(species_count) * species_count as f32;
+        let origin = self.grid_origin(species_count);
+        Rect::new(
+            origin.x - HEADER_SIZE - 4.0,
+            origin.y - HEADER_SIZE - 4.0,
+            grid + HEADER_SIZE + 8.0,
+            grid + HEADER_SIZE + LABEL_HEIGHT + 8.0,
+        )
+    }
+
+    fn lock_label(&self, species_count: usize) -> Rect {
+        let panel = self.panel(species_count);
+        Rect::new(panel.x, panel.bottom() - LABEL_HEIGHT - 4.0, panel.w, LABEL_HEIGHT)
+    }
+
+    /// Whether `position` is over the panel, so clicks there don't reach the
+    /// world. Always false while hidden.
+    pub fn contains(&self, position: Vec2, species_count: usize) -> bool {
+        self.visible && self.panel(species_count).contains(position)
+    }
+
+    fn cell_at(&self, position: Vec2, species_count: usize) -> Option<(usize, usize)> {
+        let cell = Self::cell_size(species_count);
+        let offset = (position - self.grid_origin(species_count)) / cell;
+        let in_grid = |value: f32| value >= 0.0 && (value as usize) < species_count;
+        (in_grid(offset.x) && in_grid(offset.y)).then_some((offset.y as usize, offset.x as usize))
+    }
+
+    // Sets a cell, and its mirror under the symmetric lock
+    fn set(&self, matrix: &mut InteractionMatrix, (source, target): (usize, usize), value: f32) -> Vec<InteractionChange> {
+        let value = value.clamp(-LIMIT, LIMIT);
+        let mut cells = vec![(source, target)];
+        if self.symmetric && source != target {
+            cells.push((target, source));
+        }
+        cells
+            .into_iter()
+            .map(|(source, target)| {
+                let before = matrix.get(source, target);
+                matrix.set(source, target, value);
+                InteractionChange {
+                    source,
+                    target,
+                    before,
+                    after: value,
+                }
+            })
+            .collect()
+    }
+
+    /// A click on the panel: left selects a cell and starts dragging it,
+    /// right resets it to zero, and either toggles the symmetric lock on its
+    /// label.
+    pub fn press(&mut self, button: MouseButton, position: Vec2, matrix: &mut InteractionMatrix) -> Vec<InteractionChange> {
+        let species_count = matrix.species_count();
+        if self.lock_label(species_count).contains(position) {
+            self.symmetric = !self.symmetric;
+            return Vec::new();
+        }
+        let Some(cell) = self.cell_at(position, species_count) else {
+            return Vec::new();
+        };
+        self.selected = Some(cell);
+        match button {
+            MouseButton::Left => {
+                self.drag = Some(Drag {
+                    cell,
+                    start_y: position.y,
+                    start_value: matrix.get(cell.0, cell.1),
+                });
+                Vec::new()
+            }
+            MouseButton::Right => self.set(matrix, cell, 0.0),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.drag = None;
+    }
+
+    /// Follows the mouse while dragging: up raises the strength, down
+    /// lowers it.
+    pub fn drag_to(&mut self, position: Vec2, matrix: &mut InteractionMatrix) -> Vec<InteractionChange> {
+        match &self.drag {
+            Some(drag) if drag.cell.0 < matrix.species_count() && drag.cell.1 < matrix.species_count() => {
+                let value = drag.start_value + (drag.start_y - position.y) * DRAG_PER_PIXEL;
+                self.set(matrix, drag.cell, value)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Scrolling over a cell selects it and changes its strength.
+    pub fn scroll(&mut self, position: Vec2, amount: f32, matrix: &mut InteractionMatrix) -> Vec<InteractionChange> {
+        let Some(cell) = self.cell_at(position, matrix.species_count()) else {
+            return Vec::new();
+        };
+        self.selected = Some(cell);
+        self.set(matrix, cell, matrix.get(cell.0, cell.1) + amount * SCROLL_STEP)
+    }
+
+    /// Changes the selected cell by `delta`, for keyboard adjustment.
+    pub fn adjust_selected(&mut self, delta: f32, matrix: &mut InteractionMatrix) -> Vec<InteractionChange> {
+        match self.selected {
+            Some(cell) if cell.0 < matrix.species_count() && cell.1 < matrix.species_count() => {
+                self.set(matrix, cell, matrix.get(cell.0, cell.1) + delta)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        matrix: &InteractionMatrix,
+        species: &SpeciesRegistry,
+    ) -> GameResult {
+        let n = matrix.species_count();
+        let cell = Self::cell_size(n);
+        let origin = self.grid_origin(n);
+        let species_color = |i: usize| {
+            species
+                .get(i as SpeciesId)
+                .map_or(Color::new(0.5, 0.5, 0.5, 1.0), |species| Color::from(species.color))
+        };
+
+        let mut mesh = MeshBuilder::new();
+        mesh.rectangle(DrawMode::fill(), self.panel(n), Color::new(0.0, 0.0, 0.0, 0.8))?;
+        for i in 0..n {
+            let offset = i as f32 * cell;
+            // Column header above, row header to the left
+            mesh.rectangle(
+                DrawMode::fill(),
+                Rect::new(origin.x + offset + 1.0, origin.y - HEADER_SIZE, cell - 2.0, HEADER_SIZE - 2.0),
+                species_color(i),
+            )?;
+            mesh.rectangle(
+                DrawMode::fill(),
+                Rect::new(origin.x - HEADER_SIZE, origin.y + offset + 1.0, HEADER_SIZE - 2.0, cell - 2.0),
+                species_color(i),
+            )?;
+        }
+        for source in 0..n {
+            for target in 0..n {
+                // Green attracts, red repels, brighter is stronger
+                let value = matrix.get(source, target);
+                let strength = (value.abs() / LIMIT).min(1.0);
+                let color = if value >= 0.0 {
+                    Color::new(0.1, 0.15 + 0.75 * strength, 0.1, 1.0)
+                } else {
+                    Color::new(0.15 + 0.75 * strength, 0.1, 0.1, 1.0)
+                };
+                let bounds = Rect::new(
+                    origin.x + target as f32 * cell + 1.0,
+                    origin.y + source as f32 * cell + 1.0,
+                    cell - 2.0,
+                    cell - 2.0,
+                );
+                mesh.rectangle(DrawMode::fill(), bounds, color)?;
+                if self.selected == Some((source, target)) {
+                    mesh.rectangle(DrawMode::stroke(2.0), bounds, Color::WHITE)?;
+                }
+            }
+        }
+        canvas.draw(&Mesh::from_data(ctx, mesh.build()), DrawParam::default());
+
+        for source in 0..n {
+            for target in 0..n {
+                let mut text = Text::new(format!("{:.2}", matrix.get(source, target)));
+                text.set_scale((cell * 0.32).max(8.0)).set_layout(TextLayout::center());
+                let centre = origin + Vec2::new((target as f32 + 0.5) * cell, (source as f32 + 0.5) * cell);
+                canvas.draw(&text, DrawParam::default().dest(centre).color(Color::WHITE));
+            }
+        }
+
+        let label = self.lock_label(n);
+        let mut text = Text::new(format!(
+            "Symmetric lock: {} (Y)",
+            if self.symmetric { "ON" } else { "OFF" }
+        ));
+        text.set_scale(14.0);
+        canvas.draw(&text, DrawParam::default().dest(Vec2::new(label.x + 4.0, label.y + 3.0)).color(Color::WHITE));
+        Ok(())
+    }
+}
@@ -1,18 +1,14 @@
 use crate::utils::math::Vec2;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ParticleType {
-    Red,
-    Blue,
-    Green,
-    NeonPink,
-}
+pub mod species;
+pub use species::{Species, SpeciesId, SpeciesRegistry};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Particle {
     pub position: Vec2,
     pub velocity: Vec2,
-    pub particle_type: ParticleType,
+    pub species: SpeciesId,
     pub mass: f32,
     pub radius: f32,
 }
@@ -21,26 +17,16 @@ impl Particle {
     pub fn new(
         position: Vec2,
         velocity: Vec2,
-        particle_type: ParticleType,
+        species: SpeciesId,
         mass: f32,
         radius: f32,
     ) -> Self {
         Self {
             position,
             velocity,
-            particle_type,
-            mass: match particle_type {
-                ParticleType::Red => mass,
-                ParticleType::Blue => mass * 1.2, // Slightly heavier blue particles
-                ParticleType::Green => mass * 0.8, // Lighter green particles
-                ParticleType::NeonPink => mass * 0.7, // Lightest particle - fast and agile
-            },
-            radius: match particle_type {
-                ParticleType::Red => radius,
-                ParticleType::Blue => radius * 1.1, // Slightly larger blue particles
-                ParticleType::Green => radius * 0.9, // Smaller green particles
-                ParticleType::NeonPink => radius * 1.3, // Larger pink particles for visibility
-            },
+            species,
+            mass,
+            radius,
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::Particle;
+use crate::simulation::interaction_matrix::MAX_SPECIES;
+use crate::utils::math::Vec2;
+
+/// Compact identifier of a species: its index in the `SpeciesRegistry`.
+pub type SpeciesId = u8;
+
+// Ids of the built-in species in `SpeciesRegistry::default()`
+pub const RED: SpeciesId = 0;
+pub const BLUE: SpeciesId = 1;
+pub const GREEN: SpeciesId = 2;
+pub const NEON_PINK: SpeciesId = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Species {
+    pub name: String,
+    pub color: [f32; 4], // RGBA, each channel in 0.0..=1.0
+    pub mass_multiplier: f32,
+    pub radius_multiplier: f32,
+}
+
+impl Species {
+    pub fn new(name: &str, color: [f32; 4], mass_multiplier: f32, radius_multiplier: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            color,
+            mass_multiplier,
+            radius_multiplier,
+        }
+    }
+}
+
+/// The set of species a world knows about, indexed by `SpeciesId`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SpeciesRegistry {
+    species: Vec<Species>,
+}
+
+impl SpeciesRegistry {
+    pub fn new() -> Self {
+        Self { species: Vec::new() }
+    }
+
+    /// Reads a JSON array of species definitions.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let registry: Self = serde_json::from_str(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if registry.len() > MAX_SPECIES {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} species defined, at most {} are supported", registry.len(), MAX_SPECIES),
+            ));
+        }
+        Ok(registry)
+    }
+
+    /// Adds a species and returns its id, or `None` if the registry is full.
+    pub fn register(&mut self, species: Species) -> Option<SpeciesId> {
+        if self.species.len() >= MAX_SPECIES {
+            return None;
+        }
+        self.species.push(species);
+        Some((self.species.len() - 1) as SpeciesId)
+    }
+
+    /// Removes a species and returns it. Ids below `id` are unchanged; every
+    /// later species moves down by one.
+    pub fn remove(&mut self, id: SpeciesId) -> Option<Species> {
+        ((id as usize) < self.species.len()).then(|| self.species.remove(id as usize))
+    }
+
+    pub fn get(&self, id: SpeciesId) -> Option<&Species> {
+        self.species.get(id as usize)
+    }
+
+    pub fn find(&self, name: &str) -> Option<SpeciesId> {
+        self.species
+            .iter()
+            .position(|species| species.name == name)
+            .map(|index| index as SpeciesId)
+    }
+
+    pub fn len(&self) -> usize {
+        self.species.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.species.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (SpeciesId, &Species)> {
+        self.species
+            .iter()
+            .enumerate()
+            .map(|(index, species)| (index as SpeciesId, species))
+    }
+
+    pub fn name(&self, id: SpeciesId) -> String {
+        match self.get(id) {
+            Some(species) => species.name.clone(),
+            None => format!("Species {}", id),
+        }
+    }
+
+    /// Creates a particle of the given species, scaling the base mass and
+    /// radius by the species multipliers.
+    pub fn create_particle(
+        &self,
+        position: Vec2,
+        velocity: Vec2,
+        species: SpeciesId,
+        mass: f32,
+        radius: f32,
+    ) -> Particle {
+        let (mass_multiplier, radius_multiplier) = match self.get(species) {
+            Some(s) => (s.mass_multiplier, s.radius_multiplier),
+            None => (1.0, 1.0),
+        };
+        Particle::new(
+            position,
+            velocity,
+            species,
+            mass * mass_multiplier,
+            radius * radius_multiplier,
+        )
+    }
+}
+
+impl Default for SpeciesRegistry {
+    fn default() -> Self {
+        Self {
+            species: vec![
+                Species::new("Red", [1.0, 0.0, 0.0, 1.0], 1.0, 1.0),
+                Species::new("Blue", [0.0, 0.0, 1.0, 1.0], 1.2, 1.1), // Slightly heavier and larger
+                Species::new("Green", [0.0, 1.0, 0.0, 1.0], 0.8, 0.9), // Lighter and smaller
+                Species::new("Pink", [1.0, 0.0, 0.5, 1.0], 0.7, 1.3), // Lightest, larger for visibility
+            ],
+        }
+    }
+}
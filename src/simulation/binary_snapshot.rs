@@ -0,0 +1,399 @@
+//! Compact binary snapshot format for large worlds.
+//!
+//! Layout (all numbers little-endian):
+//!
+//! ```text
+//! magic         8 bytes  "PLSNAPB\0"
+//! version       u32      BINARY_SNAPSHOT_VERSION
+//! width         f32
+//! height        f32
+//! physics       u32 length + UTF-8 JSON of PhysicsParams
+//! species       u32 count, then per species:
+//!                 u16 name length + UTF-8 name, 4 x f32 colour,
+//!                 f32 mass multiplier, f32 radius multiplier
+//! matrix        u32 species count, then count * count entries (row-major):
+//!                 f32 strength, f32 max radius (NaN = world default),
+//!                 f32 min radius
+//!               (version 1 files have the strengths only)
+//! particles     u64 count, then per particle (25 bytes):
+//!                 f32 x, f32 y, f32 vx, f32 vy, u8 species, f32 mass, f32 radius
+//! ```
+//!
+//! The physics block is kept as JSON because it is tiny and grows with every
+//! new simulation setting; the bulk of the file is the packed particle array.
+
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::snapshot::{SnapshotError, WorldSnapshot, SNAPSHOT_VERSION};
+use super::interaction_matrix::{valid_max_radius, valid_min_radius, valid_radius_pair};
+use super::{InteractionMatrix, PhysicsParams, MAX_SPECIES, MIN_SPECIES};
+use crate::particle::{Particle, Species, SpeciesRegistry};
+use crate::utils::math::Vec2;
+
+pub const MAGIC: [u8; 8] = *b"PLSNAPB\0";
+pub const BINARY_SNAPSHOT_VERSION: u32 = 2;
+// Oldest version the reader still understands
+const MIN_BINARY_SNAPSHOT_VERSION: u32 = 1;
+
+// Upper bound on the physics block so a corrupt length can't trigger a huge allocation
+const MAX_PHYSICS_BLOCK: u32 = 64 * 1024;
+// Particles are read in place, so only reserve this many up front
+const MAX_PREALLOCATED_PARTICLES: u64 = 1 << 20;
+
+/// Everything in a snapshot except the particles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotHeader {
+    pub width: f32,
+    pub height: f32,
+    pub physics: PhysicsParams,
+    pub species: SpeciesRegistry,
+    pub interaction_matrix: InteractionMatrix,
+}
+
+/// Writes a binary snapshot one particle at a time.
+///
+/// The particle count is part of the header, so it must be known up front;
+/// `finish` reports an error if a different number of particles was written.
+pub struct SnapshotWriter<W: Write> {
+    inner: W,
+    remaining: u64,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    pub fn new(mut inner: W, header: &SnapshotHeader, particle_count: u64) -> Result<Self, SnapshotError> {
+        inner.write_all(&MAGIC)?;
+        write_u32(&mut inner, BINARY_SNAPSHOT_VERSION)?;
+        write_f32(&mut inner, header.width)?;
+        write_f32(&mut inner, header.height)?;
+
+        let physics = serde_json::to_vec(&header.physics)?;
+        write_u32(&mut inner, physics.len() as u32)?;
+        inner.write_all(&physics)?;
+
+        write_u32(&mut inner, header.species.len() as u32)?;
+        for (_, species) in header.species.iter() {
+            let name = species.name.as_bytes();
+            if name.len() > u16::MAX as usize {
+                return Err(SnapshotError::Invalid(format!(
+                    "species name of {} bytes is too long",
+                    name.len()
+                )));
+            }
+            write_u16(&mut inner, name.len() as u16)?;
+            inner.write_all(name)?;
+            for channel in species.color {
+                write_f32(&mut inner, channel)?;
+            }
+            write_f32(&mut inner, species.mass_multiplier)?;
+            write_f32(&mut inner, species.radius_multiplier)?;
+        }
+
+        let matrix = &header.interaction_matrix;
+        write_u32(&mut inner, matrix.species_count() as u32)?;
+        for source in 0..matrix.species_count() {
+            for target in 0..matrix.species_count() {
+                write_f32(&mut inner, matrix.get(source, target))?;
+                write_f32(&mut inner, matrix.max_radius(source, target).unwrap_or(f32::NAN))?;
+                write_f32(&mut inner, matrix.min_radius(source, target))?;
+            }
+        }
+
+        write_u64(&mut inner, particle_count)?;
+
+        Ok(Self {
+            inner,
+            remaining: particle_count,
+        })
+    }
+
+    pub fn write_particle(&mut self, particle: &Particle) -> Result<(), SnapshotError> {
+        if self.remaining == 0 {
+            return Err(SnapshotError::Invalid(
+                "more particles written than declared in the header".to_string(),
+            ));
+        }
+        write_f32(&mut self.inner, particle.position.x)?;
+        write_f32(&mut self.inner, particle.position.y)?;
+        write_f32(&mut self.inner, particle.velocity.x)?;
+        write_f32(&mut self.inner, particle.velocity.y)?;
+        self.inner.write_all(&[particle.species])?;
+        write_f32(&mut self.inner, particle.mass)?;
+        write_f32(&mut self.inner, particle.radius)?;
+        self.remaining -= 1;
+        Ok(())
+    }
+
+    /// Flushes the writer and returns the underlying stream.
+    pub fn finish(mut self) -> Result<W, SnapshotError> {
+        if self.remaining != 0 {
+            return Err(SnapshotError::Invalid(format!(
+                "{} declared particles were never written",
+                self.remaining
+            )));
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Reads a binary snapshot, decoding the header eagerly and the particles on
+/// demand. Also usable as an iterator over the particles.
+pub struct SnapshotReader<R: Read> {
+    inner: R,
+    header: SnapshotHeader,
+    particle_count: u64,
+    remaining: u64,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    pub fn new(mut inner: R) -> Result<Self, SnapshotError> {
+        let mut magic = [0u8; 8];
+        read_exact(&mut inner, &mut magic)?;
+        if magic != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = read_u32(&mut inner)?;
+        if !(MIN_BINARY_SNAPSHOT_VERSION..=BINARY_SNAPSHOT_VERSION).contains(&version) {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let width = read_f32(&mut inner)?;
+        let height = read_f32(&mut inner)?;
+
+        let physics_len = read_u32(&mut inner)?;
+        if physics_len > MAX_PHYSICS_BLOCK {
+            return Err(SnapshotError::Invalid(format!(
+                "physics block of {} bytes exceeds {} bytes",
+                physics_len, MAX_PHYSICS_BLOCK
+            )));
+        }
+        let mut physics = vec![0u8; physics_len as usize];
+        read_exact(&mut inner, &mut physics)?;
+        let physics: PhysicsParams = serde_json::from_slice(&physics)?;
+
+        let species_count = read_u32(&mut inner)? as usize;
+        if species_count > MAX_SPECIES {
+            return Err(SnapshotError::Invalid(format!(
+                "{} species exceeds the maximum of {}",
+                species_count, MAX_SPECIES
+            )));
+        }
+        let mut species = SpeciesRegistry::new();
+        for _ in 0..species_count {
+            let name_len = read_u16(&mut inner)? as usize;
+            let mut name = vec![0u8; name_len];
+            read_exact(&mut inner, &mut name)?;
+            let name = String::from_utf8(name)
+                .map_err(|_| SnapshotError::Invalid("species name is not UTF-8".to_string()))?;
+            let mut color = [0.0; 4];
+            for channel in color.iter_mut() {
+                *channel = read_f32(&mut inner)?;
+            }
+            let mass_multiplier = read_f32(&mut inner)?;
+            let radius_multiplier = read_f32(&mut inner)?;
+            species.register(Species {
+                name,
+                color,
+                mass_multiplier,
+                radius_multiplier,
+            });
+        }
+
+        let matrix_size = read_u32(&mut inner)? as usize;
+        if !(MIN_SPECIES..=MAX_SPECIES).contains(&matrix_size) {
+            return Err(SnapshotError::Invalid(format!(
+                "interaction matrix size {} outside supported range {}..={}",
+                matrix_size, MIN_SPECIES, MAX_SPECIES
+            )));
+        }
+        let mut interaction_matrix = InteractionMatrix::new(matrix_size);
+        for source in 0..matrix_size {
+            for target in 0..matrix_size {
+                interaction_matrix.set(source, target, read_f32(&mut inner)?);
+                if version >= 2 {
+                    let max_radius = read_f32(&mut inner)?;
+                    let min_radius = read_f32(&mut inner)?;
+                    let max_radius_ok = max_radius.is_nan() || valid_max_radius(max_radius);
+                    let max_radius = if max_radius.is_nan() { None } else { Some(max_radius) };
+                    if !max_radius_ok || !valid_min_radius(min_radius) || !valid_radius_pair(min_radius, max_radius) {
+                        return Err(SnapshotError::Invalid(format!(
+                            "invalid radii {} / {:?} for species pair ({}, {})",
+                            min_radius, max_radius, source, target
+                        )));
+                    }
+                    interaction_matrix.set_max_radius(source, target, max_radius);
+                    interaction_matrix.set_min_radius(source, target, min_radius);
+                }
+            }
+        }
+
+        let particle_count = read_u64(&mut inner)?;
+
+        Ok(Self {
+            inner,
+            header: SnapshotHeader {
+                width,
+                height,
+                physics,
+                species,
+                interaction_matrix,
+            },
+            particle_count,
+            remaining: particle_count,
+        })
+    }
+
+    pub fn header(&self) -> &SnapshotHeader {
+        &self.header
+    }
+
+    pub fn particle_count(&self) -> u64 {
+        self.particle_count
+    }
+
+    /// Reads the next particle, or `None` once all declared particles are read.
+    pub fn read_particle(&mut self) -> Result<Option<Particle>, SnapshotError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let position = Vec2::new(read_f32(&mut self.inner)?, read_f32(&mut self.inner)?);
+        let velocity = Vec2::new(read_f32(&mut self.inner)?, read_f32(&mut self.inner)?);
+        let mut species = [0u8; 1];
+        read_exact(&mut self.inner, &mut species)?;
+        let species = species[0];
+        if species as usize >= self.header.species.len() {
+            return Err(SnapshotError::Invalid(format!(
+                "particle refers to unknown species {}",
+                species
+            )));
+        }
+        let mass = read_f32(&mut self.inner)?;
+        let radius = read_f32(&mut self.inner)?;
+        self.remaining -= 1;
+        Ok(Some(Particle::new(position, velocity, species, mass, radius)))
+    }
+
+    /// Reads the remaining particles and assembles the full snapshot.
+    pub fn into_snapshot(mut self) -> Result<WorldSnapshot, SnapshotError> {
+        let mut particles =
+            Vec::with_capacity(self.remaining.min(MAX_PREALLOCATED_PARTICLES) as usize);
+        while let Some(particle) = self.read_particle()? {
+            particles.push(particle);
+        }
+        let header = self.header;
+        let snapshot = WorldSnapshot {
+            version: SNAPSHOT_VERSION,
+            width: header.width,
+            height: header.height,
+            physics: header.physics,
+            species: header.species,
+            interaction_matrix: header.interaction_matrix,
+            particles,
+        };
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+}
+
+impl<R: Read> Iterator for SnapshotReader<R> {
+    type Item = Result<Particle, SnapshotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_particle() {
+            Ok(Some(particle)) => Some(Ok(particle)),
+            Ok(None) => None,
+            Err(e) => {
+                // Stop after the first error instead of reading garbage
+                self.remaining = 0;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl WorldSnapshot {
+    pub fn header(&self) -> SnapshotHeader {
+        SnapshotHeader {
+            width: self.width,
+            height: self.height,
+            physics: self.physics,
+            species: self.species.clone(),
+            interaction_matrix: self.interaction_matrix.clone(),
+        }
+    }
+
+    pub fn write_binary_to<W: Write>(&self, writer: W) -> Result<W, SnapshotError> {
+        let mut writer = SnapshotWriter::new(writer, &self.header(), self.particles.len() as u64)?;
+        for particle in &self.particles {
+            writer.write_particle(particle)?;
+        }
+        writer.finish()
+    }
+
+    pub fn read_binary_from<R: Read>(reader: R) -> Result<Self, SnapshotError> {
+        SnapshotReader::new(reader)?.into_snapshot()
+    }
+
+    pub fn write_binary(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let file = std::fs::File::create(path)?;
+        self.write_binary_to(BufWriter::new(file))?;
+        Ok(())
+    }
+
+    pub fn read_binary(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let file = std::fs::File::open(path)?;
+        Self::read_binary_from(BufReader::new(file))
+    }
+}
+
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), SnapshotError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => SnapshotError::Truncated,
+        _ => SnapshotError::Io(e),
+    })
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, SnapshotError> {
+    let mut buf = [0u8; 2];
+    read_exact(reader, &mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, SnapshotError> {
+    let mut buf = [0u8; 4];
+    read_exact(reader, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, SnapshotError> {
+    let mut buf = [0u8; 8];
+    read_exact(reader, &mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f32<R: Read>(reader: &mut R) -> Result<f32, SnapshotError> {
+    let mut buf = [0u8; 4];
+    read_exact(reader, &mut buf)?;
+    Ok(f32::from_le_bytes(buf))
+}
+
+fn write_u16<W: Write>(writer: &mut W, value: u16) -> Result<(), SnapshotError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> Result<(), SnapshotError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<(), SnapshotError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_f32<W: Write>(writer: &mut W, value: f32) -> Result<(), SnapshotError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
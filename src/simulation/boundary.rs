@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::math::Vec2;
+
+/// What happens to particles at the edges of the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BoundaryMode {
+    /// Walls: particles bounce back, losing energy by the collision damping.
+    #[default]
+    Reflect,
+    /// Torus: particles leaving through one edge come back through the
+    /// opposite one, and distances are measured across the edges.
+    Wrap,
+    /// Particles are removed once their centre leaves the world.
+    Absorb,
+}
+
+impl BoundaryMode {
+    pub const ALL: [BoundaryMode; 3] = [BoundaryMode::Reflect, BoundaryMode::Wrap, BoundaryMode::Absorb];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BoundaryMode::Reflect => "reflect",
+            BoundaryMode::Wrap => "wrap",
+            BoundaryMode::Absorb => "absorb",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Vector from `from` to `to` in a `width` x `height` world. On a torus
+    /// this is the shortest one over all periodic images of `to` (the
+    /// minimum image convention).
+    pub fn displacement(self, from: Vec2, to: Vec2, width: f32, height: f32) -> Vec2 {
+        let offset = to - from;
+        match self {
+            BoundaryMode::Wrap => Vec2::new(
+                offset.x - width * (offset.x / width).round(),
+                offset.y - height * (offset.y / height).round(),
+            ),
+            BoundaryMode::Reflect | BoundaryMode::Absorb => offset,
+        }
+    }
+}
+
+/// Maps `position` into `[0, width) x [0, height)` on a torus.
+pub fn wrap_position(position: Vec2, width: f32, height: f32) -> Vec2 {
+    Vec2::new(wrap_coordinate(position.x, width), wrap_coordinate(position.y, height))
+}
+
+fn wrap_coordinate(value: f32, size: f32) -> f32 {
+    let wrapped = value.rem_euclid(size);
+    // rem_euclid of a tiny negative value can round up to `size` itself
+    if wrapped >= size {
+        0.0
+    } else {
+        wrapped
+    }
+}
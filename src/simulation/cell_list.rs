@@ -0,0 +1,118 @@
+use super::quadtree::Bounds;
+use super::spatial_index::SpatialIndex;
+use crate::utils::math::Vec2;
+
+// Upper bound on cells per axis, so a tiny cell size can't allocate a huge grid
+const MAX_CELLS_PER_AXIS: usize = 1024;
+
+/// Uniform grid of square cells, rebuilt from scratch with a counting sort.
+///
+/// Entries are stored contiguously, grouped by cell, so a query reads a few
+/// short runs of memory instead of chasing pointers. With the cell size equal
+/// to the query radius, a query visits at most 3×3 cells.
+///
+/// Positions outside the bounds are filed under the nearest border cell; the
+/// exact distance check in `neighbors_within` keeps results correct.
+pub struct CellList {
+    bounds: Bounds,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    // Entries of cell `c` are `entries[cell_start[c]..cell_start[c + 1]]`
+    cell_start: Vec<usize>,
+    entries: Vec<(usize, Vec2)>,
+    cells: Vec<usize>, // Cell of each position, kept between builds to avoid reallocating
+}
+
+impl CellList {
+    pub fn new(bounds: Bounds, cell_size: f32) -> Self {
+        let cell_size = cell_size
+            .max(bounds.width / MAX_CELLS_PER_AXIS as f32)
+            .max(bounds.height / MAX_CELLS_PER_AXIS as f32)
+            .max(f32::MIN_POSITIVE);
+        let columns = ((bounds.width / cell_size).ceil() as usize).clamp(1, MAX_CELLS_PER_AXIS);
+        let rows = ((bounds.height / cell_size).ceil() as usize).clamp(1, MAX_CELLS_PER_AXIS);
+
+        CellList {
+            bounds,
+            cell_size,
+            columns,
+            rows,
+            cell_start: vec![0; columns * rows + 1],
+            entries: Vec::new(),
+            cells: Vec::new(),
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    // Column or row of a coordinate, clamped to the grid. NaN maps to 0.
+    fn axis_cell(&self, value: f32, origin: f32, count: usize) -> usize {
+        (((value - origin) / self.cell_size).max(0.0) as usize).min(count - 1)
+    }
+
+    fn cell_of(&self, position: Vec2) -> usize {
+        let column = self.axis_cell(position.x, self.bounds.x, self.columns);
+        let row = self.axis_cell(position.y, self.bounds.y, self.rows);
+        row * self.columns + column
+    }
+}
+
+impl SpatialIndex for CellList {
+    fn build(&mut self, positions: &[Vec2]) {
+        // Count the entries of each cell
+        let mut cells = std::mem::take(&mut self.cells);
+        cells.clear();
+        cells.extend(positions.iter().map(|&position| self.cell_of(position)));
+
+        self.cell_start.iter_mut().for_each(|start| *start = 0);
+        for &cell in &cells {
+            self.cell_start[cell + 1] += 1;
+        }
+
+        // Prefix sums give each cell's first slot
+        for c in 1..self.cell_start.len() {
+            self.cell_start[c] += self.cell_start[c - 1];
+        }
+
+        // Scatter, keeping index order within a cell
+        let mut next = self.cell_start.clone();
+        self.entries.clear();
+        self.entries.resize(positions.len(), (0, Vec2::new(0.0, 0.0)));
+        for (i, (&cell, &position)) in cells.iter().zip(positions).enumerate() {
+            self.entries[next[cell]] = (i, position);
+            next[cell] += 1;
+        }
+
+        self.cells = cells;
+    }
+
+    fn neighbors_within(&self, center: Vec2, radius: f32, found: &mut Vec<usize>) {
+        if radius.is_nan() || radius < 0.0 {
+            return;
+        }
+        let radius_sq = radius * radius;
+        let first_column = self.axis_cell(center.x - radius, self.bounds.x, self.columns);
+        let last_column = self.axis_cell(center.x + radius, self.bounds.x, self.columns);
+        let first_row = self.axis_cell(center.y - radius, self.bounds.y, self.rows);
+        let last_row = self.axis_cell(center.y + radius, self.bounds.y, self.rows);
+
+        for row in first_row..=last_row {
+            // Cells of a row are contiguous, so each row is a single run
+            let start = self.cell_start[row * self.columns + first_column];
+            let end = self.cell_start[row * self.columns + last_column + 1];
+            found.extend(
+                self.entries[start..end]
+                    .iter()
+                    .filter(|(_, position)| (*position - center).length_squared() <= radius_sq)
+                    .map(|(index, _)| *index),
+            );
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use rayon::prelude::*;
+
+use super::World;
+use crate::utils::math::Vec2;
+
+impl World {
+    // Resolves overlapping particles, where `contacts[i]` lists every particle
+    // overlapping particle i (so each pair appears once from each side).
+    //
+    // Each iteration is a parallel per-particle gather over the state left by
+    // the previous one. Both sides of a pair compute the same impulse and
+    // separation from that state and take equal and opposite shares, so
+    // every pair is resolved once and momentum and the centre of mass are
+    // conserved, whatever the scheduling.
+    pub(super) fn resolve_collisions(&mut self, contacts: &[Vec<usize>]) {
+        if contacts.iter().all(Vec::is_empty) {
+            return;
+        }
+
+        for _ in 0..self.physics.collision_iterations {
+            let deltas: Vec<(Vec2, Vec2)> = contacts
+                .par_iter()
+                .enumerate()
+                .map(|(i, touching)| {
+                    let mut velocity = Vec2::new(0.0, 0.0);
+                    let mut position = Vec2::new(0.0, 0.0);
+                    for &j in touching {
+                        let (a, b) = (i.min(j), i.max(j));
+                        let Some((impulse, separation)) = self.contact(a, b) else {
+                            continue;
+                        };
+                        let (ma, mb) = (self.particles[a].mass, self.particles[b].mass);
+                        if i == a {
+                            velocity += impulse * (1.0 / ma);
+                            position += separation * (mb / (ma + mb));
+                        } else {
+                            velocity -= impulse * (1.0 / mb);
+                            position -= separation * (ma / (ma + mb));
+                        }
+                    }
+                    (velocity, position)
+                })
+                .collect();
+
+            for (particle, (velocity, position)) in self.particles.iter_mut().zip(deltas) {
+                particle.velocity += velocity;
+                particle.position += position;
+            }
+        }
+    }
+
+    // Impulse on particle `a` from its contact with `b` (`b` gets the
+    // opposite), and the vector that would separate them if `a` moved along
+    // it alone. None once they no longer overlap.
+    fn contact(&self, a: usize, b: usize) -> Option<(Vec2, Vec2)> {
+        let (pa, pb) = (&self.particles[a], &self.particles[b]);
+        let offset = self.displacement(pb.position, pa.position);
+        let distance_sq = offset.length_squared();
+        let min_distance = pa.radius + pb.radius;
+        if distance_sq >= min_distance * min_distance {
+            return None;
+        }
+
+        // Coincident particles get pushed apart along x
+        let distance = distance_sq.sqrt();
+        let normal = if distance > 0.0 {
+            offset * (1.0 / distance)
+        } else {
+            Vec2::new(1.0, 0.0)
+        };
+
+        // Only pairs moving towards each other bounce
+        let approach = (pa.velocity - pb.velocity).dot(normal);
+        let impulse = if approach < 0.0 {
+            let reduced_mass = 1.0 / (1.0 / pa.mass + 1.0 / pb.mass);
+            normal * (-(1.0 + self.physics.restitution) * approach * reduced_mass)
+        } else {
+            Vec2::new(0.0, 0.0)
+        };
+
+        Some((impulse, normal * (min_distance - distance)))
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use super::World;
+use crate::utils::math::Vec2;
+
+/// Number of bins in each species' speed histogram.
+pub const SPEED_HISTOGRAM_BINS: usize = 16;
+
+/// Physical state of a world at one instant, from `World::diagnostics`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub particle_count: usize,
+    /// `Σ m v² / 2`.
+    pub kinetic_energy: f32,
+    /// `Σ m v`.
+    pub momentum: Vec2,
+    pub mean_speed: f32,
+    /// Mass-weighted mean position, or the origin for an empty world.
+    pub centre_of_mass: Vec2,
+    /// Kinetic energy per particle of the motion relative to the centre of
+    /// mass: the temperature in units of the Boltzmann constant by 2D
+    /// equipartition. Bulk drift doesn't count as heat.
+    pub temperature: f32,
+    /// Width of a speed histogram bin. The bins cover 0 up to the fastest
+    /// particle's speed.
+    pub speed_bin_width: f32,
+    /// Indexed by species id.
+    pub species: Vec<SpeciesDiagnostics>,
+}
+
+/// Velocity distribution of the particles of one species.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeciesDiagnostics {
+    pub count: usize,
+    pub mean_velocity: Vec2,
+    pub mean_speed: f32,
+    pub speed_std_dev: f32,
+    /// Particles per speed bin, `SPEED_HISTOGRAM_BINS` bins of
+    /// `Diagnostics::speed_bin_width`.
+    pub speed_histogram: Vec<usize>,
+}
+
+// Running sums for one species, in f64 so large worlds don't lose precision
+#[derive(Default)]
+struct SpeciesSums {
+    count: usize,
+    velocity: (f64, f64),
+    speed: f64,
+    speed_sq: f64,
+}
+
+impl World {
+    /// Energy, momentum, temperature and velocity statistics of the
+    /// particles as they are now.
+    pub fn diagnostics(&self) -> Diagnostics {
+        let particles = &self.particles;
+        let mut mass = 0.0f64;
+        let mut kinetic_energy = 0.0f64;
+        let mut momentum = (0.0f64, 0.0f64);
+        let mut weighted_position = (0.0f64, 0.0f64);
+        let mut speed_sum = 0.0f64;
+        let mut max_speed = 0.0f32;
+        let mut species = Vec::new();
+        species.resize_with(self.species.len(), SpeciesSums::default);
+
+        for particle in particles {
+            let m = particle.mass as f64;
+            let (vx, vy) = (particle.velocity.x as f64, particle.velocity.y as f64);
+            let speed = particle.velocity.length();
+            mass += m;
+            kinetic_energy += 0.5 * m * (vx * vx + vy * vy);
+            momentum.0 += m * vx;
+            momentum.1 += m * vy;
+            weighted_position.0 += m * particle.position.x as f64;
+            weighted_position.1 += m * particle.position.y as f64;
+            speed_sum += speed as f64;
+            max_speed = max_speed.max(speed);
+
+            if let Some(sums) = species.get_mut(particle.species as usize) {
+                sums.count += 1;
+                sums.velocity.0 += vx;
+                sums.velocity.1 += vy;
+                sums.speed += speed as f64;
+                sums.speed_sq += speed as f64 * speed as f64;
+            }
+        }
+
+        let count = particles.len();
+        let per_particle = |total: f64| if count > 0 { total / count as f64 } else { 0.0 };
+        let per_mass = |total: f64| if mass > 0.0 { total / mass } else { 0.0 };
+
+        // Kinetic energy of the centre of mass is P² / 2M
+        let bulk_energy = per_mass(0.5 * (momentum.0 * momentum.0 + momentum.1 * momentum.1));
+        let temperature = per_particle((kinetic_energy - bulk_energy).max(0.0));
+
+        let speed_bin_width = max_speed / SPEED_HISTOGRAM_BINS as f32;
+        let mut histograms = vec![vec![0; SPEED_HISTOGRAM_BINS]; species.len()];
+        for particle in particles {
+            if let Some(histogram) = histograms.get_mut(particle.species as usize) {
+                let bin = if speed_bin_width > 0.0 {
+                    (particle.velocity.length() / speed_bin_width) as usize
+                } else {
+                    0
+                };
+                histogram[bin.min(SPEED_HISTOGRAM_BINS - 1)] += 1;
+            }
+        }
+
+        Diagnostics {
+            particle_count: count,
+            kinetic_energy: kinetic_energy as f32,
+            momentum: Vec2::new(momentum.0 as f32, momentum.1 as f32),
+            mean_speed: per_particle(speed_sum) as f32,
+            centre_of_mass: Vec2::new(per_mass(weighted_position.0) as f32, per_mass(weighted_position.1) as f32),
+            temperature: temperature as f32,
+            speed_bin_width,
+            species: species
+                .into_iter()
+                .zip(histograms)
+                .map(|(sums, speed_histogram)| {
+                    let n = sums.count.max(1) as f64;
+                    let mean_speed = sums.speed / n;
+                    let variance = (sums.speed_sq / n - mean_speed * mean_speed).max(0.0);
+                    SpeciesDiagnostics {
+                        count: sums.count,
+                        mean_velocity: Vec2::new((sums.velocity.0 / n) as f32, (sums.velocity.1 / n) as f32),
+                        mean_speed: mean_speed as f32,
+                        speed_std_dev: variance.sqrt() as f32,
+                        speed_histogram,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
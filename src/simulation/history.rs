@@ -0,0 +1,293 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+
+use super::params::PhysicsParams;
+use super::snapshot::{SnapshotError, WorldSnapshot};
+use super::{InteractionMatrix, World};
+use crate::particle::{Particle, Species};
+
+/// Memory a `History` may use when none is given to `History::new`.
+pub const DEFAULT_HISTORY_BUDGET: usize = 64 * 1024 * 1024;
+
+/// One interaction strength changed from `before` to `after`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InteractionChange {
+    pub source: usize,
+    pub target: usize,
+    pub before: f32,
+    pub after: f32,
+}
+
+/// An undoable edit of a world, holding what it needs to be applied again
+/// and to be reverted.
+///
+/// Commands are pushed after the edit has already been made, so `apply` is
+/// only used for redo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Strengths changed together, e.g. a cell and its mirror.
+    SetInteractions(Vec<InteractionChange>),
+    SetMatrix {
+        before: InteractionMatrix,
+        after: InteractionMatrix,
+    },
+    SetPhysics {
+        before: PhysicsParams,
+        after: PhysicsParams,
+    },
+    /// Particles appended starting at index `first`. Like `RemoveParticles`
+    /// this refers to particles by index, so it must be dropped with
+    /// `History::forget_particle_edits` once particles are removed any other
+    /// way.
+    SpawnParticles { first: usize, particles: Vec<Particle> },
+    /// Particles removed from the given indices, in ascending index order.
+    RemoveParticles(Vec<(usize, Particle)>),
+    /// The whole state replaced, by a preset load, reset or snapshot load.
+    ReplaceState {
+        before: Box<WorldSnapshot>,
+        after: Box<WorldSnapshot>,
+    },
+}
+
+impl Command {
+    pub fn apply(&self, world: &mut World) -> Result<(), SnapshotError> {
+        match self {
+            Command::SetInteractions(changes) => {
+                for change in changes {
+                    world
+                        .get_interaction_matrix_mut()
+                        .set(change.source, change.target, change.after);
+                }
+            }
+            Command::SetMatrix { after, .. } => world.set_interaction_matrix(after.clone()),
+            Command::SetPhysics { after, .. } => *world.get_physics_mut() = *after,
+            Command::SpawnParticles { first, particles } => {
+                let first = (*first).min(world.particle_count());
+                for (offset, particle) in particles.iter().enumerate() {
+                    world.insert_particle(first + offset, particle.clone());
+                }
+            }
+            Command::RemoveParticles(removed) => {
+                for &(index, _) in removed.iter().rev() {
+                    if index < world.particle_count() {
+                        world.remove_particle(index);
+                    }
+                }
+            }
+            Command::ReplaceState { after, .. } => world.restore(after.as_ref().clone())?,
+        }
+        Ok(())
+    }
+
+    /// Undoes the command. Particles that moved since keep their current
+    /// positions; only the edit itself is taken back.
+    pub fn revert(&self, world: &mut World) -> Result<(), SnapshotError> {
+        match self {
+            Command::SetInteractions(changes) => {
+                // Reverse order, so a cell changed twice ends at its first value
+                for change in changes.iter().rev() {
+                    world
+                        .get_interaction_matrix_mut()
+                        .set(change.source, change.target, change.before);
+                }
+            }
+            Command::SetMatrix { before, .. } => world.set_interaction_matrix(before.clone()),
+            Command::SetPhysics { before, .. } => *world.get_physics_mut() = *before,
+            Command::SpawnParticles { first, particles } => {
+                for index in (*first..first + particles.len()).rev() {
+                    if index < world.particle_count() {
+                        world.remove_particle(index);
+                    }
+                }
+            }
+            Command::RemoveParticles(removed) => {
+                for (index, particle) in removed {
+                    world.insert_particle((*index).min(world.particle_count()), particle.clone());
+                }
+            }
+            Command::ReplaceState { before, .. } => world.restore(before.as_ref().clone())?,
+        }
+        Ok(())
+    }
+
+    /// Short description for console feedback, e.g. "spawn of 1 particle".
+    pub fn describe(&self) -> String {
+        let particles = |count: usize| format!("{} particle{}", count, if count == 1 { "" } else { "s" });
+        match self {
+            Command::SetInteractions(_) => "matrix edit".to_string(),
+            Command::SetMatrix { .. } => "matrix change".to_string(),
+            Command::SetPhysics { .. } => "physics change".to_string(),
+            Command::SpawnParticles { particles: spawned, .. } => format!("spawn of {}", particles(spawned.len())),
+            Command::RemoveParticles(removed) => format!("deletion of {}", particles(removed.len())),
+            Command::ReplaceState { .. } => "state change".to_string(),
+        }
+    }
+
+    /// Approximate memory held by the command, in bytes.
+    pub fn memory_size(&self) -> usize {
+        let heap = match self {
+            Command::SetInteractions(changes) => changes.len() * size_of::<InteractionChange>(),
+            Command::SetMatrix { before, after } => matrix_size(before) + matrix_size(after),
+            Command::SetPhysics { .. } => 0,
+            Command::SpawnParticles { particles, .. } => particles.len() * size_of::<Particle>(),
+            Command::RemoveParticles(removed) => removed.len() * size_of::<(usize, Particle)>(),
+            Command::ReplaceState { before, after } => snapshot_size(before) + snapshot_size(after),
+        };
+        size_of::<Command>() + heap
+    }
+}
+
+// Strength, minimum radius and optional maximum radius per species pair
+fn matrix_size(matrix: &InteractionMatrix) -> usize {
+    let pairs = matrix.species_count() * matrix.species_count();
+    pairs * (2 * size_of::<f32>() + size_of::<Option<f32>>())
+}
+
+fn snapshot_size(snapshot: &WorldSnapshot) -> usize {
+    size_of::<WorldSnapshot>()
+        + snapshot.particles.len() * size_of::<Particle>()
+        + snapshot.species.len() * size_of::<Species>()
+        + matrix_size(&snapshot.interaction_matrix)
+}
+
+/// Undo and redo stacks of `Command`s, bounded by a memory budget.
+///
+/// When the commands held exceed the budget the oldest undo steps are
+/// dropped; a single command larger than the whole budget is not kept.
+#[derive(Debug, Clone)]
+pub struct History {
+    undo: VecDeque<Command>,
+    redo: Vec<Command>,
+    budget: usize,
+    memory_used: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_BUDGET)
+    }
+}
+
+impl History {
+    /// Creates an empty history that holds at most `budget` bytes of commands.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            budget,
+            memory_used: 0,
+        }
+    }
+
+    /// Records an edit that has just been made. Anything that could be
+    /// redone is discarded.
+    pub fn push(&mut self, command: Command) {
+        self.clear_redo();
+        self.memory_used += command.memory_size();
+        self.undo.push_back(command);
+        self.enforce_budget();
+    }
+
+    /// Like `push`, but folds interaction changes into the latest command
+    /// when it changed the same cells, so a drag becomes one undo step.
+    pub fn push_or_merge(&mut self, command: Command) {
+        if let (Some(Command::SetInteractions(latest)), Command::SetInteractions(changes), true) =
+            (self.undo.back_mut(), &command, self.redo.is_empty())
+        {
+            let same_cells = latest.len() == changes.len()
+                && latest
+                    .iter()
+                    .zip(changes)
+                    .all(|(a, b)| (a.source, a.target) == (b.source, b.target));
+            if same_cells {
+                for (latest, change) in latest.iter_mut().zip(changes) {
+                    latest.after = change.after;
+                }
+                return;
+            }
+        }
+        self.push(command);
+    }
+
+    /// Reverts the latest command and returns it, or `None` when there is
+    /// nothing to undo. A command that fails to revert is dropped.
+    pub fn undo(&mut self, world: &mut World) -> Result<Option<&Command>, SnapshotError> {
+        let Some(command) = self.undo.pop_back() else {
+            return Ok(None);
+        };
+        if let Err(error) = command.revert(world) {
+            self.memory_used -= command.memory_size();
+            return Err(error);
+        }
+        self.redo.push(command);
+        Ok(self.redo.last())
+    }
+
+    /// Applies the latest undone command again and returns it, or `None`
+    /// when there is nothing to redo. A command that fails to apply is
+    /// dropped.
+    pub fn redo(&mut self, world: &mut World) -> Result<Option<&Command>, SnapshotError> {
+        let Some(command) = self.redo.pop() else {
+            return Ok(None);
+        };
+        if let Err(error) = command.apply(world) {
+            self.memory_used -= command.memory_size();
+            return Err(error);
+        }
+        self.undo.push_back(command);
+        Ok(self.undo.back())
+    }
+
+    /// Drops every spawn and deletion from both stacks and returns how many
+    /// were dropped. Call it when particles are removed outside the history,
+    /// e.g. absorbed by the walls, as their indices no longer point at the
+    /// particles they recorded.
+    pub fn forget_particle_edits(&mut self) -> usize {
+        let count = self.undo.len() + self.redo.len();
+        let keep = |command: &Command| {
+            !matches!(command, Command::SpawnParticles { .. } | Command::RemoveParticles(_))
+        };
+        self.undo.retain(keep);
+        self.redo.retain(keep);
+        self.memory_used = self.undo.iter().chain(&self.redo).map(Command::memory_size).sum();
+        count - self.undo.len() - self.redo.len()
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Approximate bytes held by both stacks.
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.memory_used = 0;
+    }
+
+    fn clear_redo(&mut self) {
+        for command in self.redo.drain(..) {
+            self.memory_used -= command.memory_size();
+        }
+    }
+
+    fn enforce_budget(&mut self) {
+        while self.memory_used > self.budget {
+            match self.undo.pop_front() {
+                Some(command) => self.memory_used -= command.memory_size(),
+                None => break,
+            }
+        }
+    }
+}
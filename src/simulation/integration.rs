@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+use super::boundary::{self, BoundaryMode};
+use super::snapshot::{SnapshotError, WorldSnapshot};
+use super::World;
+use crate::utils::math::Vec2;
+
+/// How `World::update` advances positions and velocities over a step.
+/// Particles accelerate by force over mass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Integrator {
+    /// Velocity from the force, then position from the new velocity. One
+    /// force evaluation per step; first order, but energy stays bounded.
+    #[default]
+    SemiImplicitEuler,
+    /// Position from the current velocity and acceleration, then velocity
+    /// from the mean of the old and new accelerations. Two force evaluations
+    /// per step; second order and time reversible.
+    VelocityVerlet,
+    /// Classic fourth order Runge-Kutta. Four force evaluations per step.
+    Rk4,
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 3] = [Integrator::SemiImplicitEuler, Integrator::VelocityVerlet, Integrator::Rk4];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::SemiImplicitEuler => "euler",
+            Integrator::VelocityVerlet => "verlet",
+            Integrator::Rk4 => "rk4",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|integrator| integrator.name() == name)
+    }
+}
+
+/// Total energy over a run of one integrator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnergyDrift {
+    pub integrator: Integrator,
+    pub steps: u64,
+    pub initial_energy: f32,
+    pub final_energy: f32,
+    /// Largest distance of the energy from its initial value after any step.
+    pub max_deviation: f32,
+}
+
+impl EnergyDrift {
+    /// Change in energy over the run, relative to the initial energy.
+    pub fn relative_drift(&self) -> f32 {
+        (self.final_energy - self.initial_energy) / self.initial_energy.abs()
+    }
+}
+
+/// Runs every integrator for `steps` steps of `dt` from `snapshot` and
+/// reports how far each lets the total energy drift. Friction is turned off
+/// and collisions made elastic, so with a symmetric matrix the energy only
+/// changes through integration error and the separation of overlapping
+/// particles.
+pub fn compare_integrators(snapshot: &WorldSnapshot, dt: f32, steps: u64) -> Result<Vec<EnergyDrift>, SnapshotError> {
+    Integrator::ALL
+        .into_iter()
+        .map(|integrator| {
+            let mut world = World::from_snapshot(snapshot.clone())?;
+            let physics = world.get_physics_mut();
+            physics.integrator = integrator;
+            physics.friction_half_life = None;
+            physics.collision_damping = 1.0;
+            physics.restitution = 1.0;
+            Ok(world.energy_drift(dt, steps))
+        })
+        .collect()
+}
+
+impl World {
+    /// Steps the world `steps` times by `dt`, tracking its total energy.
+    pub fn energy_drift(&mut self, dt: f32, steps: u64) -> EnergyDrift {
+        let initial_energy = self.total_energy();
+        let mut final_energy = initial_energy;
+        let mut max_deviation: f32 = 0.0;
+        for _ in 0..steps {
+            self.update(dt);
+            final_energy = self.total_energy();
+            max_deviation = max_deviation.max((final_energy - initial_energy).abs());
+        }
+        EnergyDrift {
+            integrator: self.physics.integrator,
+            steps,
+            initial_energy,
+            final_energy,
+            max_deviation,
+        }
+    }
+
+    // Advances positions and velocities by `dt`, given the forces at the
+    // current positions. Boundaries and friction are applied by the caller.
+    pub(super) fn integrate(&mut self, forces: &[Vec2], dt: f32) {
+        match self.physics.integrator {
+            Integrator::SemiImplicitEuler => {
+                for (particle, &force) in self.particles.iter_mut().zip(forces) {
+                    particle.velocity += force * (dt / particle.mass);
+                    particle.position += particle.velocity * dt;
+                }
+            }
+            Integrator::VelocityVerlet => {
+                let start = self.accelerations_from(forces);
+                for (particle, &acceleration) in self.particles.iter_mut().zip(&start) {
+                    particle.position += particle.velocity * dt + acceleration * (0.5 * dt * dt);
+                }
+                let positions = self.positions();
+                let end = self.accelerations_at(&positions);
+                for ((particle, &a0), &a1) in self.particles.iter_mut().zip(&start).zip(&end) {
+                    particle.velocity += (a0 + a1) * (0.5 * dt);
+                }
+            }
+            Integrator::Rk4 => {
+                // Each stage starts from the initial state along the slope of
+                // the previous one
+                let stage = |start: &[Vec2], slope: &[Vec2], h: f32| -> Vec<Vec2> {
+                    start.iter().zip(slope).map(|(&x, &dx)| x + dx * h).collect()
+                };
+                let x1 = self.positions();
+                let v1: Vec<Vec2> = self.particles.iter().map(|particle| particle.velocity).collect();
+                let a1 = self.accelerations_from(forces);
+
+                let (x2, v2) = (stage(&x1, &v1, 0.5 * dt), stage(&v1, &a1, 0.5 * dt));
+                let a2 = self.accelerations_at(&x2);
+                let (x3, v3) = (stage(&x1, &v2, 0.5 * dt), stage(&v1, &a2, 0.5 * dt));
+                let a3 = self.accelerations_at(&x3);
+                let (x4, v4) = (stage(&x1, &v3, dt), stage(&v1, &a3, dt));
+                let a4 = self.accelerations_at(&x4);
+
+                for (i, particle) in self.particles.iter_mut().enumerate() {
+                    particle.position = x1[i] + (v1[i] + (v2[i] + v3[i]) * 2.0 + v4[i]) * (dt / 6.0);
+                    particle.velocity = v1[i] + (a1[i] + (a2[i] + a3[i]) * 2.0 + a4[i]) * (dt / 6.0);
+                }
+            }
+        }
+    }
+
+    fn accelerations_from(&self, forces: &[Vec2]) -> Vec<Vec2> {
+        self.particles
+            .iter()
+            .zip(forces)
+            .map(|(particle, &force)| force * (1.0 / particle.mass))
+            .collect()
+    }
+
+    // Accelerations with the particles moved to `positions`, which may lie
+    // outside a wrapping world part way through a step
+    fn accelerations_at(&mut self, positions: &[Vec2]) -> Vec<Vec2> {
+        let positions: Vec<Vec2> = if self.physics.boundary == BoundaryMode::Wrap {
+            positions
+                .iter()
+                .map(|&position| boundary::wrap_position(position, self.width, self.height))
+                .collect()
+        } else {
+            positions.to_vec()
+        };
+        let mut forces = Vec::new();
+        self.evaluate_forces(&positions, &mut forces);
+        self.accelerations_from(&forces)
+    }
+}
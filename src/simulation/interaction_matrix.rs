@@ -1,101 +1,431 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use crate::particle::SpeciesId;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const MIN_SPECIES: usize = 2;
+pub const MAX_SPECIES: usize = 16;
+
+/// Fraction of entries left non-zero by `MatrixDistribution::Sparse`.
+pub const SPARSE_DENSITY: f32 = 0.3;
+
+/// Self-repulsion range of `MatrixDistribution::DiagonalRepulsive`.
+pub const SELF_REPULSION: std::ops::Range<f32> = -1.0..-0.2;
+
+/// How `InteractionMatrix::random` draws strengths. Every strength is in
+/// -1..1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MatrixDistribution {
+    /// Every entry independently.
+    #[default]
+    Uniform,
+    /// Each pair of species treats each other the same way.
+    Symmetric,
+    /// Predator and prey: whatever pulls `a` towards `b` pushes `b` away
+    /// from `a` just as hard. Self-interactions are drawn independently,
+    /// as a zero diagonal leaves nothing holding a species together.
+    Antisymmetric,
+    /// Uniform, but only `SPARSE_DENSITY` of the entries are non-zero.
+    Sparse,
+    /// Uniform between species, while every species repels itself.
+    DiagonalRepulsive,
+}
+
+impl MatrixDistribution {
+    pub const ALL: [MatrixDistribution; 5] = [
+        MatrixDistribution::Uniform,
+        MatrixDistribution::Symmetric,
+        MatrixDistribution::Antisymmetric,
+        MatrixDistribution::Sparse,
+        MatrixDistribution::DiagonalRepulsive,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MatrixDistribution::Uniform => "uniform",
+            MatrixDistribution::Symmetric => "symmetric",
+            MatrixDistribution::Antisymmetric => "antisymmetric",
+            MatrixDistribution::Sparse => "sparse",
+            MatrixDistribution::DiagonalRepulsive => "diagonal_repulsive",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|distribution| distribution.name() == name)
+    }
+}
+
+/// Square matrix of interaction strengths between species.
+///
+/// `get(source, target)` is the strength with which `source` is pulled
+/// towards (positive) or pushed away from (negative) `target`.
+///
+/// Each pair can also carry its own radii. `max_radius` is how far `source`
+/// senses `target`, falling back to the world's interaction radius when
+/// unset. Closer than `min_radius`, `source` is pushed away from `target`
+/// whatever the strength, and the force kernel covers the band between the
+/// two radii.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "MatrixData")]
 pub struct InteractionMatrix {
-    pub red_red: f32,
-    pub red_blue: f32,
-    pub red_green: f32,
-    pub red_pink: f32,
-    pub blue_red: f32,
-    pub blue_blue: f32,
-    pub blue_green: f32,
-    pub blue_pink: f32,
-    pub green_red: f32,
-    pub green_blue: f32,
-    pub green_green: f32,
-    pub green_pink: f32,
-    pub pink_red: f32,
-    pub pink_blue: f32,
-    pub pink_green: f32,
-    pub pink_pink: f32,
+    species_count: usize,
+    values: Vec<f32>, // Row-major: values[source * species_count + target]
+    max_radius: Vec<Option<f32>>, // Same layout as `values`
+    min_radius: Vec<f32>,         // Same layout as `values`
 }
 
 impl InteractionMatrix {
-    pub fn new(
-        red_red: f32,
-        red_blue: f32,
-        red_green: f32,
-        red_pink: f32,
-        blue_red: f32,
-        blue_blue: f32,
-        blue_green: f32,
-        blue_pink: f32,
-        green_red: f32,
-        green_blue: f32,
-        green_green: f32,
-        green_pink: f32,
-        pink_red: f32,
-        pink_blue: f32,
-        pink_green: f32,
-        pink_pink: f32,
-    ) -> Self {
+    /// Creates a matrix for `species_count` species with every strength set to zero.
+    pub fn new(species_count: usize) -> Self {
+        assert_species_count(species_count);
         Self {
-            red_red,
-            red_blue,
-            red_green,
-            red_pink,
-            blue_red,
-            blue_blue,
-            blue_green,
-            blue_pink,
-            green_red,
-            green_blue,
-            green_green,
-            green_pink,
-            pink_red,
-            pink_blue,
-            pink_green,
-            pink_pink,
+            species_count,
+            values: vec![0.0; species_count * species_count],
+            max_radius: vec![None; species_count * species_count],
+            min_radius: vec![0.0; species_count * species_count],
         }
     }
-    
-    pub fn default() -> Self {
+
+    /// Builds a matrix from rows, where `rows[source][target]` is the strength.
+    pub fn from_rows<const N: usize>(rows: [[f32; N]; N]) -> Self {
+        assert_species_count(N);
         Self {
-            red_red: -0.2,     // Repulsion between red particles
-            red_blue: 0.15,    // Attraction from red to blue
-            red_green: -0.1,   // Slight repulsion from red to green
-            red_pink: 0.3,     // Strong attraction from red to pink
-            blue_red: 0.15,    // Attraction from blue to red
-            blue_blue: 0.1,    // Attraction between blue particles
-            blue_green: 0.05,  // Weak attraction from blue to green
-            blue_pink: -0.25,  // Strong repulsion from blue to pink
-            green_red: -0.1,   // Slight repulsion from green to red
-            green_blue: 0.05,  // Weak attraction from green to blue
-            green_green: -0.3, // Strong repulsion between green particles
-            green_pink: 0.2,   // Moderate attraction from green to pink
-            pink_red: 0.3,     // Strong attraction from pink to red
-            pink_blue: -0.25,  // Strong repulsion from pink to blue
-            pink_green: 0.2,   // Moderate attraction from pink to green
-            pink_pink: -0.4,   // Very strong repulsion between pink particles
+            species_count: N,
+            values: rows.iter().flatten().copied().collect(),
+            max_radius: vec![None; N * N],
+            min_radius: vec![0.0; N * N],
+        }
+    }
+
+    /// Creates a matrix for `species_count` species with strengths drawn
+    /// from `distribution`. The same `rng` state gives the same matrix.
+    pub fn random<R: Rng + ?Sized>(species_count: usize, rng: &mut R, distribution: MatrixDistribution) -> Self {
+        let mut matrix = Self::new(species_count);
+        matrix.randomize(rng, distribution);
+        matrix
+    }
+
+    /// Redraws every strength from `distribution`, keeping the radii.
+    pub fn randomize<R: Rng + ?Sized>(&mut self, rng: &mut R, distribution: MatrixDistribution) {
+        let n = self.species_count;
+        for source in 0..n {
+            // Row by row over the upper triangle, so mirrored distributions
+            // draw each pair once
+            for target in source..n {
+                let value = rng.gen_range(-1.0..1.0);
+                let (forward, backward) = match distribution {
+                    MatrixDistribution::Uniform => (value, rng.gen_range(-1.0..1.0)),
+                    MatrixDistribution::Symmetric => (value, value),
+                    MatrixDistribution::Antisymmetric if source == target => (value, value),
+                    MatrixDistribution::Antisymmetric => (value, -value),
+                    MatrixDistribution::Sparse => {
+                        let backward = rng.gen_range(-1.0..1.0);
+                        let keep = [rng.gen::<f32>(), rng.gen::<f32>()].map(|roll| roll < SPARSE_DENSITY);
+                        (if keep[0] { value } else { 0.0 }, if keep[1] { backward } else { 0.0 })
+                    }
+                    MatrixDistribution::DiagonalRepulsive if source == target => {
+                        let value = rng.gen_range(SELF_REPULSION);
+                        (value, value)
+                    }
+                    MatrixDistribution::DiagonalRepulsive => (value, rng.gen_range(-1.0..1.0)),
+                };
+                self.set(source, target, forward);
+                if source != target {
+                    self.set(target, source, backward);
+                }
+            }
         }
     }
+
+    /// The strengths as text, one row per line, for copying between
+    /// sessions or into a spreadsheet. Radii are left out.
+    pub fn to_text(&self) -> String {
+        let n = self.species_count;
+        let mut text = String::new();
+        for row in self.values.chunks(n) {
+            let row: Vec<String> = row.iter().map(|value| format!("{:.3}", value)).collect();
+            text.push_str(&row.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Parses strengths written by `to_text`: one row per line, separated by
+    /// spaces, tabs or commas. Blank lines are skipped. Radii are unset.
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let rows = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|field| !field.is_empty())
+                    .map(|field| field.parse::<f32>().map_err(|_| format!("not a number: {:?}", field)))
+                    .collect::<Result<Vec<f32>, String>>()
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let n = rows.len();
+        if !(MIN_SPECIES..=MAX_SPECIES).contains(&n) {
+            return Err(format!(
+                "{} rows, expected between {} and {}",
+                n, MIN_SPECIES, MAX_SPECIES
+            ));
+        }
+        if let Some((i, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != n) {
+            return Err(format!("row {} has {} values, expected {}", i + 1, row.len(), n));
+        }
+        if rows.iter().flatten().any(|value| !value.is_finite()) {
+            return Err("strengths must be finite".to_string());
+        }
+        let mut matrix = Self::new(n);
+        matrix.values = rows.into_iter().flatten().collect();
+        Ok(matrix)
+    }
+
+    pub fn species_count(&self) -> usize {
+        self.species_count
+    }
+
+    pub fn get(&self, source: usize, target: usize) -> f32 {
+        self.values[self.index(source, target)]
+    }
+
+    pub fn set(&mut self, source: usize, target: usize, value: f32) {
+        let index = self.index(source, target);
+        self.values[index] = value;
+    }
+    
+    /// How far `source` senses `target`, or `None` to use the world's
+    /// interaction radius.
+    pub fn max_radius(&self, source: usize, target: usize) -> Option<f32> {
+        self.max_radius[self.index(source, target)]
+    }
     
-    pub fn get_force(&self, source: super::ParticleType, target: super::ParticleType) -> f32 {
-        match (source, target) {
-            (super::ParticleType::Red, super::ParticleType::Red) => self.red_red,
-            (super::ParticleType::Red, super::ParticleType::Blue) => self.red_blue,
-            (super::ParticleType::Red, super::ParticleType::Green) => self.red_green,
-            (super::ParticleType::Red, super::ParticleType::NeonPink) => self.red_pink,
-            (super::ParticleType::Blue, super::ParticleType::Red) => self.blue_red,
-            (super::ParticleType::Blue, super::ParticleType::Blue) => self.blue_blue,
-            (super::ParticleType::Blue, super::ParticleType::Green) => self.blue_green,
-            (super::ParticleType::Blue, super::ParticleType::NeonPink) => self.blue_pink,
-            (super::ParticleType::Green, super::ParticleType::Red) => self.green_red,
-            (super::ParticleType::Green, super::ParticleType::Blue) => self.green_blue,
-            (super::ParticleType::Green, super::ParticleType::Green) => self.green_green,
-            (super::ParticleType::Green, super::ParticleType::NeonPink) => self.green_pink,
-            (super::ParticleType::NeonPink, super::ParticleType::Red) => self.pink_red,
-            (super::ParticleType::NeonPink, super::ParticleType::Blue) => self.pink_blue,
-            (super::ParticleType::NeonPink, super::ParticleType::Green) => self.pink_green,
-            (super::ParticleType::NeonPink, super::ParticleType::NeonPink) => self.pink_pink,
-        }
-    }
-}
\ No newline at end of file
+    pub fn set_max_radius(&mut self, source: usize, target: usize, radius: Option<f32>) {
+        assert!(
+            radius.is_none_or(valid_max_radius),
+            "max radius must be positive and finite, got {:?}",
+            radius
+        );
+        let index = self.index(source, target);
+        assert!(
+            valid_radius_pair(self.min_radius[index], radius),
+            "max radius {:?} must be above the min radius {}",
+            radius,
+            self.min_radius[index]
+        );
+        self.max_radius[index] = radius;
+    }
+    
+    /// Distance below which `source` is pushed away from `target`.
+    pub fn min_radius(&self, source: usize, target: usize) -> f32 {
+        self.min_radius[self.index(source, target)]
+    }
+    
+    pub fn set_min_radius(&mut self, source: usize, target: usize, radius: f32) {
+        assert!(valid_min_radius(radius), "min radius must not be negative, got {}", radius);
+        let index = self.index(source, target);
+        assert!(
+            valid_radius_pair(radius, self.max_radius[index]),
+            "min radius {} must be below the max radius {:?}",
+            radius,
+            self.max_radius[index]
+        );
+        self.min_radius[index] = radius;
+    }
+    
+    /// Checks that every pair's min radius is below its max radius.
+    pub fn check_radii(&self) -> Result<(), String> {
+        for source in 0..self.species_count {
+            for target in 0..self.species_count {
+                let index = self.index(source, target);
+                if !valid_radius_pair(self.min_radius[index], self.max_radius[index]) {
+                    return Err(format!(
+                        "min radius {} is not below max radius {} for species pair ({}, {})",
+                        self.min_radius[index],
+                        self.max_radius[index].unwrap_or_default(),
+                        source,
+                        target
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// The largest distance at which any pair interacts, when pairs without
+    /// their own max radius use `default_radius`.
+    pub fn largest_radius(&self, default_radius: f32) -> f32 {
+        self.max_radius
+            .iter()
+            .map(|radius| radius.unwrap_or(default_radius))
+            .fold(0.0, f32::max)
+    }
+
+    /// Changes the number of species, keeping every entry whose source and
+    /// target still exist. New rows and columns start at zero.
+    pub fn resize(&mut self, species_count: usize) {
+        assert_species_count(species_count);
+        if species_count == self.species_count {
+            return;
+        }
+
+        let mut resized = Self::new(species_count);
+        let kept = self.species_count.min(species_count);
+        for source in 0..kept {
+            for target in 0..kept {
+                let from = self.index(source, target);
+                let to = resized.index(source, target);
+                resized.values[to] = self.values[from];
+                resized.max_radius[to] = self.max_radius[from];
+                resized.min_radius[to] = self.min_radius[from];
+            }
+        }
+
+        *self = resized;
+    }
+
+    /// Drops the row and column of `species`, moving later species down by
+    /// one. The matrix keeps at least `MIN_SPECIES` rows; any row and column
+    /// freed up below that start at zero.
+    pub fn remove_species(&mut self, species: usize) {
+        assert!(species < self.species_count, "species {} out of range", species);
+        let mut removed = Self::new((self.species_count - 1).max(MIN_SPECIES));
+        let kept = (0..self.species_count).filter(|&i| i != species);
+        for (to_source, source) in kept.clone().enumerate() {
+            for (to_target, target) in kept.clone().enumerate() {
+                let from = self.index(source, target);
+                let to = removed.index(to_source, to_target);
+                removed.values[to] = self.values[from];
+                removed.max_radius[to] = self.max_radius[from];
+                removed.min_radius[to] = self.min_radius[from];
+            }
+        }
+        *self = removed;
+    }
+
+    pub fn get_force(&self, source: SpeciesId, target: SpeciesId) -> f32 {
+        self.get(source as usize, target as usize)
+    }
+
+    fn index(&self, source: usize, target: usize) -> usize {
+        assert!(
+            source < self.species_count && target < self.species_count,
+            "species pair ({}, {}) out of range for {} species",
+            source,
+            target,
+            self.species_count
+        );
+        source * self.species_count + target
+    }
+}
+
+impl Default for InteractionMatrix {
+    fn default() -> Self {
+        Self::from_rows([
+            // Red row
+            [
+                -0.2,  // Repulsion between red particles
+                0.15,  // Attraction from red to blue
+                -0.1,  // Slight repulsion from red to green
+                0.3,   // Strong attraction from red to pink
+            ],
+            // Blue row
+            [
+                0.15,  // Attraction from blue to red
+                0.1,   // Attraction between blue particles
+                0.05,  // Weak attraction from blue to green
+                -0.25, // Strong repulsion from blue to pink
+            ],
+            // Green row
+            [
+                -0.1,  // Slight repulsion from green to red
+                0.05,  // Weak attraction from green to blue
+                -0.3,  // Strong repulsion between green particles
+                0.2,   // Moderate attraction from green to pink
+            ],
+            // Pink row
+            [
+                0.3,   // Strong attraction from pink to red
+                -0.25, // Strong repulsion from pink to blue
+                0.2,   // Moderate attraction from pink to green
+                -0.4,  // Very strong repulsion between pink particles
+            ],
+        ])
+    }
+}
+
+// Unchecked shape of a deserialized matrix, validated by `TryFrom`. Radii
+// are optional as the upgrade path for version 1 snapshots, saved before
+// they existed: missing radii default to no max radius and a zero min radius.
+#[derive(Deserialize)]
+struct MatrixData {
+    species_count: usize,
+    values: Vec<f32>,
+    #[serde(default)]
+    max_radius: Option<Vec<Option<f32>>>,
+    #[serde(default)]
+    min_radius: Option<Vec<f32>>,
+}
+
+impl TryFrom<MatrixData> for InteractionMatrix {
+    type Error = String;
+
+    fn try_from(data: MatrixData) -> Result<Self, Self::Error> {
+        if !(MIN_SPECIES..=MAX_SPECIES).contains(&data.species_count) {
+            return Err(format!(
+                "species count {} outside supported range {}..={}",
+                data.species_count, MIN_SPECIES, MAX_SPECIES
+            ));
+        }
+        let entries = data.species_count * data.species_count;
+        let max_radius = data.max_radius.unwrap_or_else(|| vec![None; entries]);
+        let min_radius = data.min_radius.unwrap_or_else(|| vec![0.0; entries]);
+        for (name, len) in [
+            ("matrix", data.values.len()),
+            ("max radius", max_radius.len()),
+            ("min radius", min_radius.len()),
+        ] {
+            if len != entries {
+                return Err(format!(
+                    "expected {} {} entries for {} species, found {}",
+                    entries, name, data.species_count, len
+                ));
+            }
+        }
+        if let Some(radius) = max_radius.iter().flatten().find(|radius| !valid_max_radius(**radius)) {
+            return Err(format!("max radius must be positive and finite, got {}", radius));
+        }
+        if let Some(radius) = min_radius.iter().find(|radius| !valid_min_radius(**radius)) {
+            return Err(format!("min radius must not be negative, got {}", radius));
+        }
+        let matrix = Self {
+            species_count: data.species_count,
+            values: data.values,
+            max_radius,
+            min_radius,
+        };
+        matrix.check_radii()?;
+        Ok(matrix)
+    }
+}
+
+fn assert_species_count(species_count: usize) {
+    assert!(
+        (MIN_SPECIES..=MAX_SPECIES).contains(&species_count),
+        "species count {} outside supported range {}..={}",
+        species_count,
+        MIN_SPECIES,
+        MAX_SPECIES
+    );
+}
+
+pub(crate) fn valid_max_radius(radius: f32) -> bool {
+    radius.is_finite() && radius > 0.0
+}
+
+pub(crate) fn valid_min_radius(radius: f32) -> bool {
+    radius.is_finite() && radius >= 0.0
+}
+
+pub(crate) fn valid_radius_pair(min_radius: f32, max_radius: Option<f32>) -> bool {
+    max_radius.is_none_or(|max_radius| min_radius < max_radius)
+}
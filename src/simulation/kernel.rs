@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+
+/// Shape of the force between two particles as a function of distance.
+///
+/// `force` returns the magnitude of the pull of a particle towards one at
+/// `distance` (negative pushes it away), where `strength` is the interaction
+/// matrix entry for the pair and `radius` the interaction radius. Particles
+/// beyond `radius` never interact, whatever the kernel returns.
+///
+/// `potential` is the matching energy: zero at `radius`, with `force` as its
+/// derivative with respect to distance, so pairs with symmetric strengths
+/// conserve kinetic plus potential energy.
+pub trait ForceKernel {
+    fn force(&self, strength: f32, distance: f32, radius: f32) -> f32;
+
+    fn potential(&self, strength: f32, distance: f32, radius: f32) -> f32;
+}
+
+/// `strength * (1 - d/R)`: strongest at contact, fading to zero at the radius.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Linear;
+
+impl ForceKernel for Linear {
+    fn force(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        strength * (1.0 - distance / radius)
+    }
+
+    fn potential(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        let gap = radius - distance;
+        -strength * gap * gap / (2.0 * radius)
+    }
+}
+
+/// The classic particle life kernel. Below `beta * R` every pair repels,
+/// whatever the matrix says, rising linearly to `repulsion` at contact.
+/// Between `beta * R` and `R` the matrix strength applies through a triangle
+/// that peaks halfway between the two.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Piecewise {
+    pub beta: f32,
+    pub repulsion: f32,
+}
+
+impl Default for Piecewise {
+    fn default() -> Self {
+        Self {
+            beta: 0.3,
+            repulsion: 1.0,
+        }
+    }
+}
+
+impl ForceKernel for Piecewise {
+    fn force(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        let r = distance / radius;
+        if r < self.beta {
+            self.repulsion * (r / self.beta - 1.0)
+        } else {
+            strength * (1.0 - (2.0 * r - 1.0 - self.beta).abs() / (1.0 - self.beta))
+        }
+    }
+
+    fn potential(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        // Minus the area under the force curve from `r` out to the radius,
+        // in units of the radius
+        let r = distance / radius;
+        let half_width = (1.0 - self.beta) / 2.0;
+        let peak = self.beta + half_width;
+        let area = if r >= peak {
+            strength * (1.0 - r) * (1.0 - r) / (2.0 * half_width)
+        } else if r >= self.beta {
+            strength * (half_width - (r - self.beta) * (r - self.beta) / (2.0 * half_width))
+        } else {
+            strength * half_width - self.repulsion * (self.beta - r) * (self.beta - r) / (2.0 * self.beta)
+        };
+        -area * radius
+    }
+}
+
+/// `strength * s² / (d² + s²)`: falls off with the square of the distance,
+/// softened by `softening` so it stays finite at contact.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InverseSquare {
+    pub softening: f32,
+}
+
+impl Default for InverseSquare {
+    fn default() -> Self {
+        Self { softening: 10.0 }
+    }
+}
+
+impl ForceKernel for InverseSquare {
+    fn force(&self, strength: f32, distance: f32, _radius: f32) -> f32 {
+        let softening_sq = self.softening * self.softening;
+        strength * softening_sq / (distance * distance + softening_sq)
+    }
+
+    fn potential(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        let s = self.softening;
+        strength * s * ((distance / s).atan() - (radius / s).atan())
+    }
+}
+
+/// Lennard-Jones style: the derivative of `4ε((σ/d)^12 - strength·(σ/d)^6)`.
+/// The `(σ/d)^12` core always repels; the matrix strength scales the
+/// `(σ/d)^6` tail, which attracts for positive strengths. Distances are
+/// clamped to `0.8σ` to keep the core from blowing up.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LennardJones {
+    pub sigma: f32,
+    pub epsilon: f32,
+}
+
+impl Default for LennardJones {
+    fn default() -> Self {
+        Self {
+            sigma: 10.0,
+            epsilon: 0.5,
+        }
+    }
+}
+
+impl ForceKernel for LennardJones {
+    fn force(&self, strength: f32, distance: f32, _radius: f32) -> f32 {
+        let distance = distance.max(0.8 * self.sigma);
+        let s6 = (self.sigma / distance).powi(6);
+        4.0 * self.epsilon * (6.0 * strength * s6 - 12.0 * s6 * s6) / distance
+    }
+
+    fn potential(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        let unclamped = |distance: f32| {
+            let s6 = (self.sigma / distance).powi(6);
+            4.0 * self.epsilon * (s6 * s6 - strength * s6)
+        };
+        // Below the clamp the force is constant, so the energy is linear
+        let clamp = 0.8 * self.sigma;
+        let at = |distance: f32| {
+            if distance >= clamp {
+                unclamped(distance)
+            } else {
+                unclamped(clamp) + self.force(strength, clamp, radius) * (distance - clamp)
+            }
+        };
+        at(distance) - at(radius)
+    }
+}
+
+/// The kernel a world uses, with its parameters. Saved with the physics
+/// parameters in snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Kernel {
+    Linear(Linear),
+    Piecewise(Piecewise),
+    InverseSquare(InverseSquare),
+    LennardJones(LennardJones),
+}
+
+impl Kernel {
+    /// Every kernel with its default parameters.
+    pub fn all() -> [Kernel; 4] {
+        [
+            Kernel::Linear(Linear),
+            Kernel::Piecewise(Piecewise::default()),
+            Kernel::InverseSquare(InverseSquare::default()),
+            Kernel::LennardJones(LennardJones::default()),
+        ]
+    }
+
+    /// Checks that the kernel's parameters give finite forces.
+    pub fn check(&self) -> Result<(), String> {
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+        let valid = match self {
+            Kernel::Linear(_) => true,
+            Kernel::Piecewise(kernel) => kernel.beta > 0.0 && kernel.beta < 1.0 && kernel.repulsion.is_finite(),
+            Kernel::InverseSquare(kernel) => positive(kernel.softening),
+            Kernel::LennardJones(kernel) => positive(kernel.sigma) && kernel.epsilon.is_finite(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("invalid {} kernel parameters {:?}", self.name(), self))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kernel::Linear(_) => "linear",
+            Kernel::Piecewise(_) => "piecewise",
+            Kernel::InverseSquare(_) => "inverse_square",
+            Kernel::LennardJones(_) => "lennard_jones",
+        }
+    }
+
+    /// The kernel called `name`, with default parameters.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|kernel| kernel.name() == name)
+    }
+}
+
+impl Default for Kernel {
+    fn default() -> Self {
+        Kernel::Linear(Linear)
+    }
+}
+
+impl ForceKernel for Kernel {
+    fn force(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        match self {
+            Kernel::Linear(kernel) => kernel.force(strength, distance, radius),
+            Kernel::Piecewise(kernel) => kernel.force(strength, distance, radius),
+            Kernel::InverseSquare(kernel) => kernel.force(strength, distance, radius),
+            Kernel::LennardJones(kernel) => kernel.force(strength, distance, radius),
+        }
+    }
+
+    fn potential(&self, strength: f32, distance: f32, radius: f32) -> f32 {
+        match self {
+            Kernel::Linear(kernel) => kernel.potential(strength, distance, radius),
+            Kernel::Piecewise(kernel) => kernel.potential(strength, distance, radius),
+            Kernel::InverseSquare(kernel) => kernel.potential(strength, distance, radius),
+            Kernel::LennardJones(kernel) => kernel.potential(strength, distance, radius),
+        }
+    }
+}
@@ -1,60 +1,275 @@
-use crate::particle::Particle;
-use crate::particle::ParticleType;
+use crate::particle::{Particle, Species, SpeciesId, SpeciesRegistry};
 use crate::utils::math::Vec2;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use rayon::prelude::*;
-use std::sync::{Arc, Mutex};
+use std::path::Path;
 
+pub mod binary_snapshot;
+pub mod boundary;
+pub mod cell_list;
+pub mod collision;
+pub mod diagnostics;
+pub mod history;
+pub mod integration;
 pub mod interaction_matrix;
-mod quadtree;
-pub use interaction_matrix::InteractionMatrix;
+pub mod kernel;
+pub mod params;
+pub mod preset;
+pub mod quadtree;
+pub mod recording;
+pub mod snapshot;
+pub mod spawn;
+pub mod spatial_index;
+pub mod timestep;
+pub use boundary::BoundaryMode;
+pub use cell_list::CellList;
+pub use diagnostics::{Diagnostics, SpeciesDiagnostics};
+pub use history::{Command, History, InteractionChange};
+pub use integration::{EnergyDrift, Integrator};
+pub use interaction_matrix::{InteractionMatrix, MatrixDistribution, MAX_SPECIES, MIN_SPECIES};
+pub use kernel::{ForceKernel, Kernel};
+pub use params::{PhysicsParams, MAX_COLLISION_ITERATIONS};
+pub use preset::{Preset, PresetError};
 pub use self::quadtree::{Bounds, QuadTree};
+pub use binary_snapshot::{SnapshotHeader, SnapshotReader, SnapshotWriter};
+pub use snapshot::{SnapshotError, WorldSnapshot, SNAPSHOT_VERSION};
+pub use spatial_index::{SpatialIndex, SpatialIndexKind};
+pub use spawn::{Region, Shape, Spawn, SpeciesDistribution, SpeciesWeight, VelocityField, MAX_SPAWN_PARTICLES};
+pub use timestep::FixedTimestep;
 
-const INTERACTION_RADIUS: f32 = 100.0;
-const INTERACTION_RADIUS_SQUARED: f32 = INTERACTION_RADIUS * INTERACTION_RADIUS;
-const COLLISION_DAMPING: f32 = 0.8; // Energy loss during collision
+// Force at contact of the push inside a pair's min radius
+const MIN_RADIUS_REPULSION: f32 = 1.0;
 
 pub struct World {
     particles: Vec<Particle>,
     width: f32,
     height: f32,
     interaction_matrix: InteractionMatrix,
-    quad_tree: QuadTree,
+    species: SpeciesRegistry,
+    physics: PhysicsParams,
+    spatial_index: Box<dyn SpatialIndex>,
+    spatial_index_config: (SpatialIndexKind, f32), // Kind and radius the index was created for
+    forces: Vec<Vec2>, // Per-particle force buffer, reused between steps
+    seed: u64,
+    rng: StdRng, // Every random draw (presets, spawns) comes from here
 }
 
 impl World {
+    /// Creates a world with a random seed. Use `seed()` to find out which.
     pub fn new(width: f32, height: f32) -> Self {
+        Self::new_with_seed(width, height, rand::random())
+    }
+    
+    /// Creates a world whose random draws are fully determined by `seed`.
+    pub fn new_with_seed(width: f32, height: f32, seed: u64) -> Self {
+        let physics = PhysicsParams::default();
+        let bounds = Bounds {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        };
         Self {
             particles: Vec::new(),
             width,
             height,
             interaction_matrix: InteractionMatrix::default(),
-            quad_tree: QuadTree::new(
-                Bounds {
-                    x: 0.0,
-                    y: 0.0,
-                    width,
-                    height,
-                },
-                0,
-            ),
+            species: SpeciesRegistry::default(),
+            physics,
+            spatial_index: physics.spatial_index.create(bounds, physics.interaction_radius),
+            spatial_index_config: (physics.spatial_index, physics.interaction_radius),
+            forces: Vec::new(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+    
+    /// The seed the random number generator was last seeded with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    
+    /// Restarts the random number generator from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+    
+    /// The world's random number generator, for callers that spawn particles
+    /// and want the run to stay reproducible.
+    pub fn rng_mut(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+    
+    /// Farthest distance at which any pair of particles interacts: the
+    /// interaction radius, or a larger per-pair max radius.
+    pub fn query_radius(&self) -> f32 {
+        self.interaction_matrix.largest_radius(self.physics.interaction_radius)
+    }
+    
+    fn bounds(&self) -> Bounds {
+        Bounds {
+            x: 0.0,
+            y: 0.0,
+            width: self.width,
+            height: self.height,
         }
     }
     
-    pub fn set_interaction_matrix(&mut self, matrix: InteractionMatrix) {
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+    
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+    
+    pub fn get_physics(&self) -> &PhysicsParams {
+        &self.physics
+    }
+    
+    pub fn get_physics_mut(&mut self) -> &mut PhysicsParams {
+        &mut self.physics
+    }
+    
+    /// Captures the full state of the world.
+    pub fn snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            version: SNAPSHOT_VERSION,
+            width: self.width,
+            height: self.height,
+            physics: self.physics,
+            species: self.species.clone(),
+            interaction_matrix: self.interaction_matrix.clone(),
+            particles: self.particles.clone(),
+        }
+    }
+    
+    /// Replaces the whole world state with `snapshot`, after validating it.
+    pub fn restore(&mut self, snapshot: WorldSnapshot) -> Result<(), SnapshotError> {
+        snapshot.validate()?;
+        self.width = snapshot.width;
+        self.height = snapshot.height;
+        self.physics = snapshot.physics;
+        self.species = snapshot.species;
+        self.interaction_matrix = snapshot.interaction_matrix;
+        self.particles = snapshot.particles;
+        self.spatial_index_config = (self.physics.spatial_index, self.query_radius());
+        self.spatial_index = self.physics.spatial_index.create(self.bounds(), self.query_radius());
+        Ok(())
+    }
+    
+    pub fn from_snapshot(snapshot: WorldSnapshot) -> Result<Self, SnapshotError> {
+        let mut world = World::new(snapshot.width, snapshot.height);
+        world.restore(snapshot)?;
+        Ok(world)
+    }
+    
+    /// Writes the world state to `path`: binary for `.bin` files, JSON otherwise.
+    pub fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        self.snapshot().write(path)
+    }
+    
+    /// Replaces the world state with the snapshot at `path` (binary for
+    /// `.bin` files, JSON otherwise). On error the world is left untouched.
+    pub fn load_snapshot(&mut self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let snapshot = WorldSnapshot::read(path)?;
+        self.restore(snapshot)
+    }
+    
+    /// Replaces the interaction matrix. One smaller than the species
+    /// registry is grown to cover every species, with zeros for the pairs
+    /// it lacks.
+    pub fn set_interaction_matrix(&mut self, mut matrix: InteractionMatrix) {
+        if matrix.species_count() < self.species.len() {
+            matrix.resize(self.species.len());
+        }
         self.interaction_matrix = matrix;
     }
     
-    pub fn get_interaction_matrix(&self) -> InteractionMatrix {
-        self.interaction_matrix
+    pub fn get_interaction_matrix(&self) -> &InteractionMatrix {
+        &self.interaction_matrix
     }
     
     pub fn get_interaction_matrix_mut(&mut self) -> &mut InteractionMatrix {
         &mut self.interaction_matrix
     }
     
-    pub fn add_particle(&mut self, particle: Particle) {
+    pub fn get_species(&self) -> &SpeciesRegistry {
+        &self.species
+    }
+    
+    /// Replaces the species registry, growing or shrinking the interaction
+    /// matrix to match while keeping the strengths of species that remain.
+    pub fn set_species(&mut self, species: SpeciesRegistry) {
+        self.interaction_matrix
+            .resize(species.len().clamp(MIN_SPECIES, MAX_SPECIES));
+        self.species = species;
+    }
+    
+    /// Registers a new species and gives it a zero row and column in the
+    /// interaction matrix. Returns `None` if no more species fit.
+    pub fn register_species(&mut self, species: Species) -> Option<SpeciesId> {
+        let id = self.species.register(species)?;
+        self.interaction_matrix
+            .resize(self.species.len().max(MIN_SPECIES));
+        Some(id)
+    }
+    
+    /// Removes a species along with its particles and its row and column of
+    /// the interaction matrix. Particles of later species are renumbered to
+    /// follow their species down by one.
+    pub fn remove_species(&mut self, id: SpeciesId) -> Option<Species> {
+        let species = self.species.remove(id)?;
+        self.particles.retain(|particle| particle.species != id);
+        for particle in &mut self.particles {
+            if particle.species > id {
+                particle.species -= 1;
+            }
+        }
+        if (id as usize) < self.interaction_matrix.species_count() {
+            self.interaction_matrix.remove_species(id as usize);
+        }
+        Some(species)
+    }
+    
+    /// Adds a particle of `species`, scaling `mass` and `radius` by the
+    /// species multipliers. Returns `false`, adding nothing, if the species
+    /// isn't registered.
+    pub fn spawn_particle(&mut self, position: Vec2, velocity: Vec2, species: SpeciesId, mass: f32, radius: f32) -> bool {
+        if !self.has_species(species) {
+            return false;
+        }
+        let particle = self.species.create_particle(position, velocity, species, mass, radius);
+        self.particles.push(particle);
+        true
+    }
+    
+    /// Adds a particle as is. Returns `false`, adding nothing, if its species
+    /// isn't registered.
+    pub fn add_particle(&mut self, particle: Particle) -> bool {
+        if !self.has_species(particle.species) {
+            return false;
+        }
         self.particles.push(particle);
+        true
+    }
+    
+    // Particles of unknown species would index past the interaction matrix
+    fn has_species(&self, species: SpeciesId) -> bool {
+        (species as usize) < self.species.len()
+    }
+    
+    /// Inserts a particle at `index`, shifting later particles up by one.
+    pub fn insert_particle(&mut self, index: usize, particle: Particle) {
+        self.particles.insert(index, particle);
+    }
+    
+    /// Removes and returns the particle at `index`, shifting later particles
+    /// down by one.
+    pub fn remove_particle(&mut self, index: usize) -> Particle {
+        self.particles.remove(index)
     }
     
     pub fn clear(&mut self) {
@@ -70,448 +285,264 @@ impl World {
     }
     
     pub fn update(&mut self, dt: f32) {
-        // Rebuild quad tree
-        self.quad_tree.clear();
-        for (i, particle) in self.particles.iter().enumerate() {
-            self.quad_tree.insert(i, particle.position);
-        }
-
-        // Calculate forces in parallel
-        let forces = Arc::new(Mutex::new(vec![Vec2::new(0.0, 0.0); self.particles.len()]));
-        let collisions = Arc::new(Mutex::new(Vec::new()));
-
-        // Process particles in parallel chunks
-        self.particles.par_iter().enumerate().for_each(|(i, p1)| {
-            let mut local_forces = vec![Vec2::new(0.0, 0.0); self.particles.len()];
-            
-            // Query nearby particles from quad tree
-            let mut neighbors = Vec::new();
-            let query_bounds = Bounds {
-                x: p1.position.x - INTERACTION_RADIUS,
-                y: p1.position.y - INTERACTION_RADIUS,
-                width: INTERACTION_RADIUS * 2.0,
-                height: INTERACTION_RADIUS * 2.0,
-            };
-            self.quad_tree.query(&query_bounds, &mut neighbors);
+        let positions = self.positions();
+        let mut forces = std::mem::take(&mut self.forces);
+        let contacts = self.evaluate_forces(&positions, &mut forces);
 
-            for &j in &neighbors {
-                if i == j {
-                    continue;
-                }
-                
-                let force = self.calculate_interaction_force(i, j);
-                local_forces[i] += force;
-                local_forces[j] -= force;
-                
-                // Record collisions to process later (using squared distance for efficiency)
-                let dx = p1.position.x - self.particles[j].position.x;
-                let dy = p1.position.y - self.particles[j].position.y;
-                let distance_sq = dx * dx + dy * dy;
-                let min_distance = p1.radius + self.particles[j].radius;
-                let min_distance_sq = min_distance * min_distance;
-                
-                if distance_sq < min_distance_sq {
-                    collisions.lock().unwrap().push((i, j));
-                }
-            }
-
-            // Merge local forces into global forces
-            let mut global_forces = forces.lock().unwrap();
-            for (idx, force) in local_forces.into_iter().enumerate() {
-                global_forces[idx] += force;
-            }
-        });
-
-        let forces = Arc::try_unwrap(forces).unwrap().into_inner().unwrap();
-        let collisions = Arc::try_unwrap(collisions).unwrap().into_inner().unwrap();
-
-        // Process collisions sequentially
-        for (i, j) in collisions {
-            self.check_particle_collision(i, j);
-        }
+        // Push overlapping particles apart
+        self.resolve_collisions(&contacts);
         
         // Update particle positions and velocities
-        for (i, particle) in self.particles.iter_mut().enumerate() {
-            // Apply force
-            particle.velocity += forces[i] * dt;
-            
-            // Update position
-            particle.position += particle.velocity * dt;
-            
+        self.integrate(&forces, dt);
+        self.forces = forces;
+        
+        let collision_damping = self.physics.collision_damping;
+        let friction = self.physics.friction_factor(dt);
+        let boundary = self.physics.boundary;
+        let (width, height) = (self.width, self.height);
+        for particle in &mut self.particles {
             // Handle boundary collision
-            if particle.position.x - particle.radius < 0.0 {
-                particle.position.x = particle.radius;
-                particle.velocity.x *= -COLLISION_DAMPING;
-            } else if particle.position.x + particle.radius > self.width {
-                particle.position.x = self.width - particle.radius;
-                particle.velocity.x *= -COLLISION_DAMPING;
-            }
-            
-            if particle.position.y - particle.radius < 0.0 {
-                particle.position.y = particle.radius;
-                particle.velocity.y *= -COLLISION_DAMPING;
-            } else if particle.position.y + particle.radius > self.height {
-                particle.position.y = self.height - particle.radius;
-                particle.velocity.y *= -COLLISION_DAMPING;
+            match boundary {
+                BoundaryMode::Reflect => reflect_off_walls(particle, width, height, collision_damping),
+                BoundaryMode::Wrap => {
+                    particle.position = boundary::wrap_position(particle.position, width, height)
+                }
+                BoundaryMode::Absorb => {}
             }
             
-            // Apply damping
-            particle.velocity *= 0.99;
+            // Apply friction
+            particle.velocity *= friction;
         }
-    }
-    
-    fn check_particle_collision(&mut self, i: usize, j: usize) {
-        let dx = self.particles[i].position.x - self.particles[j].position.x;
-        let dy = self.particles[i].position.y - self.particles[j].position.y;
-        let distance_sq = dx * dx + dy * dy;
-        
-        let min_distance = self.particles[i].radius + self.particles[j].radius;
-        let min_distance_sq = min_distance * min_distance;
         
-        if distance_sq < min_distance_sq {
-            let distance = distance_sq.sqrt();
-            // Collision normal
-            let nx = dx / distance;
-            let ny = dy / distance;
-            
-            // Relative velocity
-            let dvx = self.particles[i].velocity.x - self.particles[j].velocity.x;
-            let dvy = self.particles[i].velocity.y - self.particles[j].velocity.y;
-            
-            // Impulse scalar
-            let impulse_scalar = 2.0 * (dvx * nx + dvy * ny) / 
-                (self.particles[i].mass + self.particles[j].mass);
-            
-            // Update velocities
-            let mut p1 = self.particles[i].clone();
-            let mut p2 = self.particles[j].clone();
-            
-            p1.velocity.x -= impulse_scalar * p2.mass * nx * COLLISION_DAMPING;
-            p1.velocity.y -= impulse_scalar * p2.mass * ny * COLLISION_DAMPING;
-            
-            p2.velocity.x += impulse_scalar * p1.mass * nx * COLLISION_DAMPING;
-            p2.velocity.y += impulse_scalar * p1.mass * ny * COLLISION_DAMPING;
-            
-            // Separate particles
-            let overlap = min_distance - distance;
-            p1.position.x += nx * overlap * 0.5;
-            p1.position.y += ny * overlap * 0.5;
-            
-            p2.position.x -= nx * overlap * 0.5;
-            p2.position.y -= ny * overlap * 0.5;
-            
-            // Update particles
-            self.particles[i] = p1;
-            self.particles[j] = p2;
+        if boundary == BoundaryMode::Absorb {
+            self.particles.retain(|particle| {
+                particle.position.x >= 0.0
+                    && particle.position.x <= width
+                    && particle.position.y >= 0.0
+                    && particle.position.y <= height
+            });
         }
     }
     
-    fn calculate_interaction_force(&self, i: usize, j: usize) -> Vec2 {
-        let p1 = &self.particles[i];
-        let p2 = &self.particles[j];
-        
-        let dx = p2.position.x - p1.position.x;
-        let dy = p2.position.y - p1.position.y;
-        let distance_sq = dx * dx + dy * dy;
-        
-        // No interaction if too far (using squared distance for efficiency)
-        if distance_sq > INTERACTION_RADIUS_SQUARED {
-            return Vec2::new(0.0, 0.0);
-        }
-        
-        // Calculate actual distance only if needed
-        // Get force strength from interaction matrix
-        let force_strength = self.interaction_matrix.get_force(p1.particle_type, p2.particle_type);
+    /// Vector from `from` to `to`, measured across the edges when the world
+    /// wraps around.
+    pub fn displacement(&self, from: Vec2, to: Vec2) -> Vec2 {
+        self.physics.boundary.displacement(from, to, self.width, self.height)
+    }
+    
+    /// Total interaction force on every particle at the current positions,
+    /// indexed like `get_particles()`.
+    pub fn compute_forces(&mut self) -> Vec<Vec2> {
+        let positions = self.positions();
+        let mut forces = Vec::new();
+        self.evaluate_forces(&positions, &mut forces);
+        forces
+    }
+    
+    /// Kinetic energy of all particles, `Σ m v² / 2`.
+    pub fn kinetic_energy(&self) -> f32 {
+        self.particles
+            .iter()
+            .map(|particle| 0.5 * particle.mass * particle.velocity.length_squared())
+            .sum()
+    }
+    
+    /// Potential energy of the interaction forces at the current positions.
+    /// Each pair contributes the mean of its two directions' potentials, so
+    /// this is only conserved (with kinetic energy) when the matrix is
+    /// symmetric.
+    pub fn potential_energy(&mut self) -> f32 {
+        let positions = self.positions();
+        self.rebuild_spatial_index(&positions);
         
-        // Skip force calculation if strength is zero
-        if force_strength == 0.0 {
-            return Vec2::new(0.0, 0.0);
+        let query_radius = self.query_radius();
+        let mut neighbors = Vec::new();
+        let mut energy = 0.0;
+        for (i, p1) in self.particles.iter().enumerate() {
+            neighbors.clear();
+            self.neighbors_of(p1.position, query_radius, &mut neighbors);
+            for &j in &neighbors {
+                if i == j {
+                    continue;
+                }
+                let p2 = &self.particles[j];
+                let distance = self.displacement(p1.position, p2.position).length();
+                energy += 0.5 * self.pair_potential(p1.species as usize, p2.species as usize, distance);
+            }
         }
-        
-        // Calculate actual distance only if needed
-        let distance = distance_sq.sqrt();
-        
-        // Normalized direction
-        let nx = dx / distance;
-        let ny = dy / distance;
-        
-        // Scale force by distance (stronger when closer)
-        let force_magnitude = force_strength * (1.0 - distance / INTERACTION_RADIUS);
-        
-        Vec2::new(nx * force_magnitude, ny * force_magnitude)
+        energy
     }
     
-    pub fn load_preset(&mut self, preset: u32) {
-        self.clear();
-        
-        match preset {
-            1 => self.create_preset_1(false),
-            2 => self.create_preset_2(false),
-            3 => self.create_preset_3(false),
-            4 => self.create_preset_4(),
-            5 => self.create_preset_5(),
-            6 => self.create_preset_6(),
-            _ => {}
+    /// Kinetic plus potential energy.
+    pub fn total_energy(&mut self) -> f32 {
+        self.kinetic_energy() + self.potential_energy()
+    }
+    
+    fn positions(&self) -> Vec<Vec2> {
+        self.particles.iter().map(|particle| particle.position).collect()
+    }
+    
+    // Fills `forces[i]` with the total force on particle i when the
+    // particles are at `positions`, and returns the particles overlapping each.
+    fn evaluate_forces(&mut self, positions: &[Vec2], forces: &mut Vec<Vec2>) -> Vec<Vec<usize>> {
+        self.rebuild_spatial_index(positions);
+        self.accumulate_forces(positions, forces)
+    }
+    
+    fn rebuild_spatial_index(&mut self, positions: &[Vec2]) {
+        // The physics parameters may have changed since the last step
+        let config = (self.physics.spatial_index, self.query_radius());
+        if config != self.spatial_index_config {
+            self.spatial_index = config.0.create(self.bounds(), config.1);
+            self.spatial_index_config = config;
         }
+        
+        self.spatial_index.build(positions);
     }
     
-    fn create_preset_1(&mut self, include_green: bool) {
-        // Red and Blue particles in different regions
-        for i in 0..100 {
-            let angle = (i as f32) * 0.2;
-            
-            // Red particles on left side
-            let x_red = self.width / 4.0 + angle.cos() * 100.0;
-            let y_red = self.height / 2.0 + angle.sin() * 100.0;
-            
-            // Blue particles on right side
-            let x_blue = 3.0 * self.width / 4.0 + angle.cos() * 100.0;
-            let y_blue = self.height / 2.0 + angle.sin() * 100.0;
-            
-            self.add_particle(Particle::new(
-                Vec2::new(x_red, y_red),
-                Vec2::new(0.0, 0.0),
-                ParticleType::Red,
-                1.0,
-                3.0,
-            ));
-            
-            self.add_particle(Particle::new(
-                Vec2::new(x_blue, y_blue),
-                Vec2::new(0.0, 0.0),
-                ParticleType::Blue,
-                1.0,
-                3.0,
-            ));
-            
-            // Add green particles in the center if requested
-            if include_green {
-                let x_green = self.width / 2.0 + (angle * 2.0).cos() * 50.0;
-                let y_green = self.height / 2.0 + (angle * 2.0).sin() * 50.0;
-                
-                self.add_particle(Particle::new(
-                    Vec2::new(x_green, y_green),
-                    Vec2::new(0.0, 0.0),
-                    ParticleType::Green,
-                    1.0,
-                    2.0,
-                ));
-            }
+    fn neighbors_of(&self, position: Vec2, radius: f32, found: &mut Vec<usize>) {
+        if self.physics.boundary == BoundaryMode::Wrap {
+            self.spatial_index
+                .neighbors_within_periodic(position, radius, self.bounds(), found);
+        } else {
+            self.spatial_index.neighbors_within(position, radius, found);
         }
     }
     
-    fn create_preset_2(&mut self, include_green: bool) {
-        // Red and Blue particles in grid pattern
-        for x in 0..20 {
-            for y in 0..15 {
-                let px = (x as f32) * (self.width / 20.0);
-                let py = (y as f32) * (self.height / 15.0);
-                
-                let particle_type = if !include_green {
-                    if (x + y) % 2 == 0 {
-                        ParticleType::Red
-                    } else {
-                        ParticleType::Blue
+    // Fills `forces[i]` with the total force on particle i and returns the
+    // particles overlapping each one. Each particle only sums the forces
+    // acting on itself, so the parallel pass shares no mutable state and its
+    // result doesn't depend on scheduling.
+    fn accumulate_forces(&self, positions: &[Vec2], forces: &mut Vec<Vec2>) -> Vec<Vec<usize>> {
+        let query_radius = self.query_radius();
+        let mut overlaps: Vec<Vec<usize>> = Vec::new();
+        self.particles
+            .par_iter()
+            .enumerate()
+            .map_init(Vec::new, |neighbors, (i, p1)| {
+                // Query particles within reach of any pair
+                neighbors.clear();
+                self.neighbors_of(positions[i], query_radius, neighbors);
+
+                let mut force = Vec2::new(0.0, 0.0);
+                let mut overlapping = Vec::new();
+                for &j in neighbors.iter() {
+                    if i == j {
+                        continue;
                     }
-                } else {
-                    match (x + y) % 3 {
-                        0 => ParticleType::Red,
-                        1 => ParticleType::Blue,
-                        _ => ParticleType::Green,
+                    
+                    let offset = self.displacement(positions[i], positions[j]);
+                    force += self.calculate_interaction_force(i, j, offset);
+                    
+                    // Record collisions to process later (using squared distance for efficiency)
+                    let min_distance = p1.radius + self.particles[j].radius;
+                    if offset.length_squared() < min_distance * min_distance {
+                        overlapping.push(j);
                     }
-                };
+                }
                 
-                self.add_particle(Particle::new(
-                    Vec2::new(px, py),
-                    Vec2::new(0.0, 0.0),
-                    particle_type,
-                    1.0,
-                    2.0,
-                ));
-            }
-        }
+                (force, overlapping)
+            })
+            .unzip_into_vecs(forces, &mut overlaps);
+        overlaps
     }
     
-    fn create_preset_3(&mut self, include_green: bool) {
-        let mut rng = rand::thread_rng();
+    // Force on particle i from particle j, which is `offset` away from it
+    fn calculate_interaction_force(&self, i: usize, j: usize, offset: Vec2) -> Vec2 {
+        let distance_sq = offset.length_squared();
         
-        for _ in 0..2000 {
-            let x = rng.gen_range(0.0..self.width);
-            let y = rng.gen_range(0.0..self.height);
-            
-            let particle_type = if include_green {
-                match rng.gen_range(0..3) {
-                    0 => ParticleType::Red,
-                    1 => ParticleType::Blue,
-                    _ => ParticleType::Green,
-                }
-            } else {
-                if rng.gen_bool(0.5) {
-                    ParticleType::Red
-                } else {
-                    ParticleType::Blue
-                }
-            };
-            
-            self.add_particle(Particle::new(
-                Vec2::new(x, y),
-                Vec2::new(0.0, 0.0),
-                particle_type,
-                1.0,
-                2.0,
-            ));
+        // Coincident particles have no direction to push along
+        if distance_sq == 0.0 {
+            return Vec2::new(0.0, 0.0);
+        }
+        
+        // No interaction if too far (using squared distance for efficiency)
+        let (source, target) = (self.particles[i].species as usize, self.particles[j].species as usize);
+        let max_radius = self.pair_max_radius(source, target);
+        if distance_sq > max_radius * max_radius {
+            return Vec2::new(0.0, 0.0);
         }
+        
+        let distance = distance_sq.sqrt();
+        offset * (self.pair_force(source, target, distance) / distance)
     }
     
-    fn create_preset_4(&mut self) {
-        // Green particles in the center with red and blue orbiting
-        for i in 0..100 {
-            let angle = (i as f32) * 0.2;
-            
-            // Green particles in the center
-            let x_green = self.width / 2.0 + angle.cos() * 50.0;
-            let y_green = self.height / 2.0 + angle.sin() * 50.0;
-            
-            // Red particles orbiting clockwise
-            let x_red = self.width / 2.0 + (angle * 2.0).cos() * 150.0;
-            let y_red = self.height / 2.0 + (angle * 2.0).sin() * 150.0;
-            
-            // Blue particles orbiting counter-clockwise
-            let x_blue = self.width / 2.0 + (angle * 2.0 + std::f32::consts::PI).cos() * 150.0;
-            let y_blue = self.height / 2.0 + (angle * 2.0 + std::f32::consts::PI).sin() * 150.0;
-            
-            // Add green particle
-            self.add_particle(Particle::new(
-                Vec2::new(x_green, y_green),
-                Vec2::new(0.0, 0.0),
-                ParticleType::Green,
-                1.0,
-                3.0,
-            ));
-            
-            // Add red particle
-            self.add_particle(Particle::new(
-                Vec2::new(x_red, y_red),
-                Vec2::new(0.0, 0.0),
-                ParticleType::Red,
-                1.0,
-                3.0,
-            ));
-            
-            // Add blue particle
-            self.add_particle(Particle::new(
-                Vec2::new(x_blue, y_blue),
-                Vec2::new(0.0, 0.0),
-                ParticleType::Blue,
-                1.0,
-                3.0,
-            ));
-        }
+    fn pair_max_radius(&self, source: usize, target: usize) -> f32 {
+        self.interaction_matrix
+            .max_radius(source, target)
+            .unwrap_or(self.physics.interaction_radius)
     }
     
-    fn create_preset_5(&mut self) {
-        let mut rng = rand::thread_rng();
-        
-        // Create 8,000 particles
-        for _ in 1..8000 {
-            let x = rng.gen_range(0.0..self.width);
-            let y = rng.gen_range(0.0..self.height);
-            
-            // Weighted distribution: 40% Red, 35% Blue, 25% Green
-            let particle_type = match rng.gen_range(0..100) {
-                0..40 => ParticleType::Red,
-                40..75 => ParticleType::Blue,
-                _ => ParticleType::Green,
-            };
-            
-            // Minimal initial velocity for stable formations
-            let vel_x = rng.gen_range(-2.0..2.0);
-            let vel_y = rng.gen_range(-2.0..2.0);
-            
-            self.add_particle(Particle::new(
-                Vec2::new(x, y),
-                Vec2::new(vel_x, vel_y),
-                particle_type,
-                1.0,
-                2.0,
-            ));
+    // Pull of a `source` particle towards a `target` one within its max radius.
+    // Inside the pair's min radius it always repels; beyond it the kernel
+    // shapes the matrix strength over the band up to the max radius.
+    fn pair_force(&self, source: usize, target: usize, distance: f32) -> f32 {
+        let max_radius = self.pair_max_radius(source, target);
+        let min_radius = self.interaction_matrix.min_radius(source, target);
+        if distance < min_radius {
+            MIN_RADIUS_REPULSION * (distance / min_radius - 1.0)
+        } else if max_radius > min_radius {
+            let force_strength = self.interaction_matrix.get(source, target);
+            self.physics
+                .kernel
+                .force(force_strength, distance - min_radius, max_radius - min_radius)
+        } else {
+            0.0
         }
-        
-        // "Orbital Clusters" interaction matrix
-        // Red particles form cores, Blue orbit around Red, Green creates bridges
-        self.interaction_matrix = InteractionMatrix {
-            red_red: -0.8,    // Strong repulsion - Red cores spread out
-            red_blue: 0.9,    // Strong attraction - Blue orbits Red
-            red_green: 0.1,   // Weak attraction - Green slightly drawn to Red
-            red_pink: 0.0,    // No pink particles in this preset
-            
-            blue_red: 0.3,    // Moderate attraction - Blue wants to orbit Red
-            blue_blue: -0.2,  // Weak repulsion - Blue particles don't clump
-            blue_green: -0.1, // Weak repulsion - Blue avoids Green slightly
-            blue_pink: 0.0,   // No pink particles in this preset
-            
-            green_red: 0.2,   // Weak attraction - Green forms bridges to Red
-            green_blue: 0.4,  // Moderate attraction - Green connects to Blue
-            green_green: -0.3, // Moderate repulsion - Green spreads out
-            green_pink: 0.0,  // No pink particles in this preset
-            
-            pink_red: 0.0,    // No pink particles in this preset
-            pink_blue: 0.0,   // No pink particles in this preset
-            pink_green: 0.0,  // No pink particles in this preset
-            pink_pink: 0.0,   // No pink particles in this preset
-        };
     }
     
-    fn create_preset_6(&mut self) {
-        let mut rng = rand::thread_rng();
-        
-        // Create 2000 particles with NeonPink included
-        for _ in 0..2000 {
-            let x = rng.gen_range(0.0..self.width);
-            let y = rng.gen_range(0.0..self.height);
-            
-            // Distribute particles: 30% Red, 30% Blue, 20% Green, 20% NeonPink
-            let particle_type = match rng.gen_range(0..100) {
-                0..30 => ParticleType::Red,
-                30..60 => ParticleType::Blue,
-                60..80 => ParticleType::Green,
-                _ => ParticleType::NeonPink,
-            };
-            
-            // Minimal initial velocity for stable formations
-            let vel_x = rng.gen_range(-10.0..10.0);
-            let vel_y = rng.gen_range(-10.0..10.0);
-            
-            self.add_particle(Particle::new(
-                Vec2::new(x, y),
-                Vec2::new(vel_x, vel_y),
-                particle_type,
-                1.0,
-                3.0,
-            ));
+    // Energy whose derivative with respect to distance is `pair_force`, zero
+    // from the max radius outwards
+    fn pair_potential(&self, source: usize, target: usize, distance: f32) -> f32 {
+        let max_radius = self.pair_max_radius(source, target);
+        if distance >= max_radius {
+            return 0.0;
         }
-        
-        // Set custom interaction matrix for NeonPink
-        self.interaction_matrix = InteractionMatrix {
-            red_red: -0.3,
-            red_blue: 0.2,
-            red_green: -0.1,
-            red_pink: 0.8,   // Strong attraction to NeonPink
-            
-            blue_red: 0.2,
-            blue_blue: 0.1,
-            blue_green: 0.05,
-            blue_pink: -0.7, // Strong repulsion from NeonPink
-            
-            green_red: -0.1,
-            green_blue: 0.05,
-            green_green: -0.3,
-            green_pink: 0.5, // Moderate attraction to NeonPink
-            
-            pink_red: 0.8,   // Strong attraction to Red
-            pink_blue: -0.7, // Strong repulsion from Blue
-            pink_green: 0.5, // Moderate attraction to Green
-            pink_pink: -0.9, // Very strong repulsion between NeonPink particles
+        let min_radius = self.interaction_matrix.min_radius(source, target);
+        let band = |distance: f32| {
+            if max_radius > min_radius {
+                let force_strength = self.interaction_matrix.get(source, target);
+                self.physics
+                    .kernel
+                    .potential(force_strength, distance, max_radius - min_radius)
+            } else {
+                0.0
+            }
         };
+        if distance < min_radius {
+            let gap = min_radius - distance;
+            MIN_RADIUS_REPULSION * gap * gap / (2.0 * min_radius) + band(0.0)
+        } else {
+            band(distance - min_radius)
+        }
     }
-}
\ No newline at end of file
+    
+    /// Loads built-in preset `preset`, numbered from 1 in the order of
+    /// `Preset::builtin`. An unknown number leaves the world untouched.
+    pub fn load_preset(&mut self, preset: u32) -> Result<(), PresetError> {
+        let builtin = preset
+            .checked_sub(1)
+            .and_then(|index| Preset::builtin().into_iter().nth(index as usize))
+            .ok_or(PresetError::Unknown(preset))?;
+        self.apply_preset(&builtin)
+    }
+}
+
+// Clamps a particle inside the walls, bouncing its velocity off any wall it hit
+fn reflect_off_walls(particle: &mut Particle, width: f32, height: f32, collision_damping: f32) {
+    if particle.position.x - particle.radius < 0.0 {
+        particle.position.x = particle.radius;
+        particle.velocity.x *= -collision_damping;
+    } else if particle.position.x + particle.radius > width {
+        particle.position.x = width - particle.radius;
+        particle.velocity.x *= -collision_damping;
+    }
+    
+    if particle.position.y - particle.radius < 0.0 {
+        particle.position.y = particle.radius;
+        particle.velocity.y *= -collision_damping;
+    } else if particle.position.y + particle.radius > height {
+        particle.position.y = height - particle.radius;
+        particle.velocity.y *= -collision_damping;
+    }
+}
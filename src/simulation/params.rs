@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use super::boundary::BoundaryMode;
+use super::integration::Integrator;
+use super::kernel::Kernel;
+use super::spatial_index::SpatialIndexKind;
+
+/// Upper bound on `PhysicsParams::collision_iterations`, so a typo can't
+/// stall every step.
+pub const MAX_COLLISION_ITERATIONS: u32 = 64;
+
+/// Physical constants of a world. Saved with snapshots so a state can be
+/// resumed with the same behaviour it was captured with.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PhysicsParams {
+    pub interaction_radius: f32,
+    pub collision_damping: f32, // Fraction of speed kept when bouncing off walls
+    /// Fraction of the approach speed two colliding particles separate with:
+    /// 1 is elastic, 0 stops them dead along the contact normal.
+    #[serde(default = "default_restitution")]
+    pub restitution: f32,
+    /// Passes of the collision solver per step. More passes settle crowded
+    /// overlaps better.
+    #[serde(default = "default_collision_iterations")]
+    pub collision_iterations: u32,
+    /// Seconds for friction to halve a particle's speed, or `None` for no
+    /// friction. Independent of the step size.
+    #[serde(default = "default_friction_half_life")]
+    pub friction_half_life: Option<f32>,
+    #[serde(default)]
+    pub spatial_index: SpatialIndexKind,
+    #[serde(default)]
+    pub boundary: BoundaryMode,
+    #[serde(default)]
+    pub kernel: Kernel,
+    #[serde(default)]
+    pub integrator: Integrator,
+}
+
+fn default_restitution() -> f32 {
+    0.6
+}
+
+fn default_collision_iterations() -> u32 {
+    4
+}
+
+// Matches the old fixed damping of 0.99 per step at 60 steps per second
+fn default_friction_half_life() -> Option<f32> {
+    Some(1.15)
+}
+
+impl PhysicsParams {
+    /// Factor friction scales velocities by over a step of `dt` seconds.
+    pub fn friction_factor(&self, dt: f32) -> f32 {
+        match self.friction_half_life {
+            Some(half_life) => 0.5f32.powf(dt / half_life),
+            None => 1.0,
+        }
+    }
+
+    /// Checks that every constant is in its supported range. Presets and
+    /// snapshots are checked with this before they are loaded.
+    pub fn check(&self) -> Result<(), String> {
+        if !(self.interaction_radius.is_finite() && self.interaction_radius > 0.0) {
+            return Err(format!("interaction radius must be positive, got {}", self.interaction_radius));
+        }
+        for (name, value) in [("collision damping", self.collision_damping), ("restitution", self.restitution)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1, got {}", name, value));
+            }
+        }
+        if let Some(half_life) = self.friction_half_life {
+            if !(half_life.is_finite() && half_life > 0.0) {
+                return Err(format!("friction half-life must be positive, got {}", half_life));
+            }
+        }
+        if self.collision_iterations > MAX_COLLISION_ITERATIONS {
+            return Err(format!(
+                "{} collision iterations, at most {} are supported",
+                self.collision_iterations, MAX_COLLISION_ITERATIONS
+            ));
+        }
+        self.kernel.check()
+    }
+}
+
+impl Default for PhysicsParams {
+    fn default() -> Self {
+        Self {
+            interaction_radius: 100.0,
+            collision_damping: 0.8,
+            restitution: default_restitution(),
+            collision_iterations: default_collision_iterations(),
+            friction_half_life: default_friction_half_life(),
+            spatial_index: SpatialIndexKind::default(),
+            boundary: BoundaryMode::default(),
+            kernel: Kernel::default(),
+            integrator: Integrator::default(),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::spawn::{Spawn, MAX_SPAWN_PARTICLES};
+use super::{InteractionMatrix, PhysicsParams, World};
+use crate::particle::{Particle, SpeciesRegistry};
+use crate::simulation::interaction_matrix::MAX_SPECIES;
+
+/// Extension of the preset files found by `Preset::scan_dir`.
+pub const PRESET_EXTENSION: &str = "json";
+
+// The presets shipped in `presets/`, compiled in so they are available
+// without the directory (the library, the headless runner, tests)
+const BUILTIN_PRESETS: [&str; 7] = [
+    include_str!("../../presets/01-red-and-blue-orbitals.json"),
+    include_str!("../../presets/02-grid-formation.json"),
+    include_str!("../../presets/03-random-distribution.json"),
+    include_str!("../../presets/04-orbiting-system.json"),
+    include_str!("../../presets/05-high-density-formation.json"),
+    include_str!("../../presets/06-neon-pink-ecosystem.json"),
+    include_str!("../../presets/07-spiral-galaxy.json"),
+];
+
+/// A preset file and the result of loading it, from `Preset::scan_dir`.
+pub type ScannedPreset = (PathBuf, Result<Preset, PresetError>);
+
+/// A complete description of a starting world: its species, interaction
+/// matrix, physics and the particles to spawn. Loading one replaces all of
+/// these, so nothing carries over from whatever was loaded before.
+///
+/// Positions are given as fractions of the world's width and height, so a
+/// preset fits any world size; lengths (radii, speeds) are in world units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// The built-in species when absent.
+    #[serde(default)]
+    pub species: SpeciesRegistry,
+    /// The built-in matrix when absent. Must cover every species.
+    #[serde(default)]
+    pub interaction_matrix: InteractionMatrix,
+    /// The default physics when absent.
+    #[serde(default)]
+    pub physics: PhysicsParams,
+    /// Run in order, each drawing from the world's random number generator.
+    #[serde(default)]
+    pub spawns: Vec<Spawn>,
+}
+
+impl Preset {
+    /// The presets in the `presets/` directory of the source tree, in file
+    /// name order.
+    pub fn builtin() -> Vec<Preset> {
+        BUILTIN_PRESETS
+            .iter()
+            .map(|json| Self::from_json(json).expect("built-in presets are valid"))
+            .collect()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PresetError> {
+        let preset: Self = serde_json::from_str(json)?;
+        preset.validate()?;
+        Ok(preset)
+    }
+
+    pub fn to_json(&self) -> Result<String, PresetError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Reads a preset file. Image mask paths are relative to the file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PresetError> {
+        let path = path.as_ref();
+        let mut preset = Self::from_json(&std::fs::read_to_string(path)?)?;
+        if let Some(dir) = path.parent() {
+            for spawn in &mut preset.spawns {
+                spawn.shape.resolve_paths(dir);
+            }
+        }
+        Ok(preset)
+    }
+
+    /// Loads every `.json` file in `dir`, in file name order. A file that
+    /// fails to load is returned as its error, so one bad file doesn't hide
+    /// the rest.
+    pub fn scan_dir(dir: impl AsRef<Path>) -> Result<Vec<ScannedPreset>, PresetError> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+            .collect();
+        paths.sort();
+        Ok(paths
+            .into_iter()
+            .map(|path| {
+                let preset = Self::load(&path);
+                (path, preset)
+            })
+            .collect())
+    }
+
+    /// Checks the physics, that the matrix covers the species and that every
+    /// spawn refers to known species and has sensible sizes, with at most
+    /// `MAX_SPAWN_PARTICLES` particles between them.
+    pub fn validate(&self) -> Result<(), PresetError> {
+        let invalid = |reason: String| Err(PresetError::Invalid(reason));
+        if self.species.is_empty() || self.species.len() > MAX_SPECIES {
+            return invalid(format!(
+                "{} species defined, between 1 and {} are supported",
+                self.species.len(),
+                MAX_SPECIES
+            ));
+        }
+        if self.species.len() > self.interaction_matrix.species_count() {
+            return invalid(format!(
+                "{} species but the interaction matrix only covers {}",
+                self.species.len(),
+                self.interaction_matrix.species_count()
+            ));
+        }
+        self.physics.check().map_err(PresetError::Invalid)?;
+        let mut total: usize = 0;
+        for spawn in &self.spawns {
+            spawn.validate(&self.species)?;
+            total = total.saturating_add(spawn.shape.particle_count().unwrap_or(usize::MAX));
+        }
+        if total > MAX_SPAWN_PARTICLES {
+            return invalid(format!("spawns ask for {} particles, at most {} are supported", total, MAX_SPAWN_PARTICLES));
+        }
+        Ok(())
+    }
+
+    /// Generates the particles of every spawn for a world of the given size.
+    fn generate(&self, rng: &mut StdRng, width: f32, height: f32) -> Result<Vec<Particle>, PresetError> {
+        let mut particles = Vec::new();
+        for spawn in &self.spawns {
+            spawn.generate(&self.species, rng, width, height, &mut particles)?;
+        }
+        Ok(particles)
+    }
+}
+
+impl World {
+    /// Replaces the particles, species, interaction matrix and physics with
+    /// those of `preset`. The world size and random number generator are
+    /// kept. On error the world is left untouched.
+    pub fn apply_preset(&mut self, preset: &Preset) -> Result<(), PresetError> {
+        preset.validate()?;
+        let particles = preset.generate(&mut self.rng, self.width, self.height)?;
+        self.particles = particles;
+        self.species = preset.species.clone();
+        self.interaction_matrix = preset.interaction_matrix.clone();
+        self.physics = preset.physics;
+        self.spatial_index_config = (self.physics.spatial_index, self.query_radius());
+        self.spatial_index = self.physics.spatial_index.create(self.bounds(), self.query_radius());
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum PresetError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Invalid(String),
+    /// No built-in preset has this number.
+    Unknown(u32),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Io(e) => write!(f, "preset I/O error: {}", e),
+            PresetError::Json(e) => write!(f, "malformed preset JSON: {}", e),
+            PresetError::Invalid(reason) => write!(f, "invalid preset: {}", reason),
+            PresetError::Unknown(preset) => write!(
+                f,
+                "no built-in preset {} (there are {})",
+                preset,
+                BUILTIN_PRESETS.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PresetError::Io(e) => Some(e),
+            PresetError::Json(e) => Some(e),
+            PresetError::Invalid(_) | PresetError::Unknown(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PresetError {
+    fn from(e: std::io::Error) -> Self {
+        PresetError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(e: serde_json::Error) -> Self {
+        PresetError::Json(e)
+    }
+}
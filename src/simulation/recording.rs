@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::params::PhysicsParams;
+use super::snapshot::{SnapshotError, WorldSnapshot};
+use super::{InteractionMatrix, World};
+use crate::particle::Particle;
+
+/// Frames between keyframes when none is given to `Recorder::with_keyframe_interval`.
+pub const DEFAULT_KEYFRAME_INTERVAL: usize = 300;
+
+/// A user action that changed the world outside of `World::update`.
+///
+/// Events carry their results (the spawned particle, the new matrix value,
+/// the state a preset produced) rather than the inputs that caused them, so
+/// replaying them never depends on random draws.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    SpawnParticle(Particle),
+    /// Particles removed from the given indices, in ascending order.
+    RemoveParticles(Vec<usize>),
+    SetInteraction { source: usize, target: usize, value: f32 },
+    SetMatrix(InteractionMatrix),
+    SetPhysics(PhysicsParams),
+    PresetLoaded { preset: u32, state: Box<WorldSnapshot> },
+    SnapshotLoaded(Box<WorldSnapshot>),
+}
+
+impl RecordedEvent {
+    pub fn apply(&self, world: &mut World) -> Result<(), SnapshotError> {
+        match self {
+            RecordedEvent::SpawnParticle(particle) => {
+                if !world.add_particle(particle.clone()) {
+                    return Err(SnapshotError::Invalid(format!(
+                        "spawned particle refers to unknown species {}",
+                        particle.species
+                    )));
+                }
+            }
+            RecordedEvent::RemoveParticles(indices) => {
+                for &index in indices.iter().rev() {
+                    if index < world.particle_count() {
+                        world.remove_particle(index);
+                    }
+                }
+            }
+            RecordedEvent::SetInteraction { source, target, value } => {
+                world.get_interaction_matrix_mut().set(*source, *target, *value)
+            }
+            RecordedEvent::SetMatrix(matrix) => world.set_interaction_matrix(matrix.clone()),
+            RecordedEvent::SetPhysics(physics) => *world.get_physics_mut() = *physics,
+            RecordedEvent::PresetLoaded { state, .. } | RecordedEvent::SnapshotLoaded(state) => {
+                world.restore(state.as_ref().clone())?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Events applied before one simulation step, followed by the step itself.
+/// `dt` is `None` for events that happened while paused at the end of a
+/// recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub events: Vec<RecordedEvent>,
+    pub dt: Option<f32>,
+}
+
+/// World state before `frame` is applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub frame: usize,
+    pub state: WorldSnapshot,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// Seed of the recorded world's random number generator.
+    pub seed: u64,
+    pub initial: WorldSnapshot,
+    pub keyframe_interval: usize,
+    pub keyframes: Vec<Keyframe>,
+    pub frames: Vec<Frame>,
+}
+
+impl Recording {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let json = serde_json::to_string(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let json = std::fs::read_to_string(path)?;
+        let recording: Self = serde_json::from_str(&json)?;
+        recording.initial.validate()?;
+        for keyframe in &recording.keyframes {
+            keyframe.state.validate()?;
+        }
+        Ok(recording)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+/// Captures a session: the starting state, then every step and user event.
+pub struct Recorder {
+    recording: Recording,
+    pending: Vec<RecordedEvent>,
+}
+
+impl Recorder {
+    pub fn new(world: &World) -> Self {
+        Self::with_keyframe_interval(world, DEFAULT_KEYFRAME_INTERVAL)
+    }
+
+    pub fn with_keyframe_interval(world: &World, keyframe_interval: usize) -> Self {
+        Self {
+            recording: Recording {
+                seed: world.seed(),
+                initial: world.snapshot(),
+                keyframe_interval: keyframe_interval.max(1),
+                keyframes: Vec::new(),
+                frames: Vec::new(),
+            },
+            pending: Vec::new(),
+        }
+    }
+
+    /// Records an event that has already been applied to the world.
+    pub fn record_event(&mut self, event: RecordedEvent) {
+        self.pending.push(event);
+    }
+
+    /// Records a `World::update(dt)` call. `world` is the state after the step,
+    /// used for keyframes.
+    pub fn record_step(&mut self, world: &World, dt: f32) {
+        self.recording.frames.push(Frame {
+            events: std::mem::take(&mut self.pending),
+            dt: Some(dt),
+        });
+
+        let frame = self.recording.frames.len();
+        if frame.is_multiple_of(self.recording.keyframe_interval) {
+            self.recording.keyframes.push(Keyframe {
+                frame,
+                state: world.snapshot(),
+            });
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.recording.frames.len()
+    }
+
+    /// Ends the recording, keeping any events recorded since the last step.
+    pub fn finish(mut self) -> Recording {
+        if !self.pending.is_empty() {
+            self.recording.frames.push(Frame {
+                events: std::mem::take(&mut self.pending),
+                dt: None,
+            });
+        }
+        self.recording
+    }
+}
+
+/// Replays a `Recording` into its own `World`.
+pub struct Player {
+    recording: Recording,
+    world: World,
+    frame: usize,
+}
+
+impl Player {
+    pub fn new(recording: Recording) -> Result<Self, SnapshotError> {
+        let world = World::from_snapshot(recording.initial.clone())?;
+        Ok(Self {
+            recording,
+            world,
+            frame: 0,
+        })
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Number of frames applied so far.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.recording.frames.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames.len()
+    }
+
+    /// Applies the next frame. Returns `false` once the recording is exhausted.
+    pub fn step(&mut self) -> Result<bool, SnapshotError> {
+        let Some(frame) = self.recording.frames.get(self.frame) else {
+            return Ok(false);
+        };
+        for event in &frame.events {
+            event.apply(&mut self.world)?;
+        }
+        if let Some(dt) = frame.dt {
+            self.world.update(dt);
+        }
+        self.frame += 1;
+        Ok(true)
+    }
+
+    /// Moves to the state before `frame` (clamped to the end of the
+    /// recording) by restoring the nearest earlier keyframe and replaying
+    /// from there.
+    pub fn seek(&mut self, frame: usize) -> Result<(), SnapshotError> {
+        let frame = frame.min(self.recording.frames.len());
+
+        // Jump to a keyframe when going backwards or when one is closer than
+        // the current position; otherwise just keep stepping forward.
+        let keyframe = self
+            .recording
+            .keyframes
+            .iter()
+            .rev()
+            .find(|keyframe| keyframe.frame <= frame);
+        let keyframe_frame = keyframe.map_or(0, |keyframe| keyframe.frame);
+        if frame < self.frame || keyframe_frame > self.frame {
+            let state = match keyframe {
+                Some(keyframe) => keyframe.state.clone(),
+                None => self.recording.initial.clone(),
+            };
+            self.world.restore(state)?;
+            self.frame = keyframe_frame;
+        }
+
+        while self.frame < frame {
+            self.step()?;
+        }
+        Ok(())
+    }
+}
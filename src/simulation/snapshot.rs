@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use super::{InteractionMatrix, PhysicsParams, MAX_SPECIES};
+use crate::particle::{Particle, SpeciesRegistry};
+
+/// Version written into every snapshot. Bump it when the layout changes.
+/// Version 2 added per-pair radii to the interaction matrix.
+pub const SNAPSHOT_VERSION: u32 = 2;
+/// Oldest version still read. Version 1 matrices load with default radii.
+pub const MIN_SNAPSHOT_VERSION: u32 = 1;
+
+/// Complete, serializable state of a `World`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub version: u32,
+    pub width: f32,
+    pub height: f32,
+    pub physics: PhysicsParams,
+    pub species: SpeciesRegistry,
+    pub interaction_matrix: InteractionMatrix,
+    pub particles: Vec<Particle>,
+}
+
+/// Extension that selects the binary format in `WorldSnapshot::read`/`write`.
+pub const BINARY_EXTENSION: &str = "bin";
+
+impl WorldSnapshot {
+    /// Reads a snapshot, using the binary format for `.bin` files and JSON
+    /// for anything else.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        if is_binary_path(path.as_ref()) {
+            Self::read_binary(path)
+        } else {
+            Self::read_json(path)
+        }
+    }
+
+    /// Writes a snapshot, using the binary format for `.bin` files and JSON
+    /// for anything else.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        if is_binary_path(path.as_ref()) {
+            self.write_binary(path)
+        } else {
+            self.write_json(path)
+        }
+    }
+
+    pub fn read_json(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let json = std::fs::read_to_string(path)?;
+        let mut snapshot: Self = serde_json::from_str(&json)?;
+        snapshot.validate()?;
+        // Older layouts are filled in while deserializing
+        snapshot.version = SNAPSHOT_VERSION;
+        Ok(snapshot)
+    }
+
+    pub fn write_json(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let json = serde_json::to_string(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Checks the version, the matrix radii and that every species
+    /// referenced exists.
+    pub fn validate(&self) -> Result<(), SnapshotError> {
+        if !(MIN_SNAPSHOT_VERSION..=SNAPSHOT_VERSION).contains(&self.version) {
+            return Err(SnapshotError::UnsupportedVersion(self.version));
+        }
+        if !(self.width > 0.0 && self.height > 0.0) {
+            return Err(SnapshotError::Invalid(format!(
+                "world size {}x{} is not positive",
+                self.width, self.height
+            )));
+        }
+        if self.species.len() > MAX_SPECIES {
+            return Err(SnapshotError::Invalid(format!(
+                "{} species, at most {} are supported",
+                self.species.len(),
+                MAX_SPECIES
+            )));
+        }
+        if self.species.len() > self.interaction_matrix.species_count() {
+            return Err(SnapshotError::Invalid(format!(
+                "{} species but the interaction matrix only covers {}",
+                self.species.len(),
+                self.interaction_matrix.species_count()
+            )));
+        }
+        self.interaction_matrix.check_radii().map_err(SnapshotError::Invalid)?;
+        self.physics.check().map_err(SnapshotError::Invalid)?;
+        if let Some(particle) = self
+            .particles
+            .iter()
+            .find(|p| p.species as usize >= self.species.len())
+        {
+            return Err(SnapshotError::Invalid(format!(
+                "particle refers to unknown species {}",
+                particle.species
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn is_binary_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == BINARY_EXTENSION)
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    BadMagic,
+    Truncated,
+    UnsupportedVersion(u32),
+    Invalid(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot I/O error: {}", e),
+            SnapshotError::Json(e) => write!(f, "malformed snapshot JSON: {}", e),
+            SnapshotError::BadMagic => write!(f, "not a binary snapshot (bad magic header)"),
+            SnapshotError::Truncated => write!(f, "snapshot file is truncated"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::Invalid(reason) => write!(f, "invalid snapshot: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e),
+            SnapshotError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Json(e)
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use super::cell_list::CellList;
+use super::quadtree::{Bounds, QuadTree};
+use crate::utils::math::Vec2;
+
+/// Answers "which particles are near this point" for the force and
+/// collision passes.
+///
+/// Implementations are exact: `neighbors_within` returns every index whose
+/// position is within `radius` of `center`, edges included, and nothing else.
+/// The order of the results is up to the implementation.
+pub trait SpatialIndex: Send + Sync {
+    /// Replaces the contents of the index with `positions`, where the
+    /// position of particle `i` is `positions[i]`.
+    fn build(&mut self, positions: &[Vec2]);
+
+    /// Adds the index of every particle within `radius` of `center` to `found`.
+    fn neighbors_within(&self, center: Vec2, radius: f32, found: &mut Vec<usize>);
+
+    /// Like `neighbors_within`, but on a torus covering `bounds`: a particle
+    /// is found if any of its periodic images is within `radius`. Assumes
+    /// every position is inside `bounds` and `radius` is at most the size of
+    /// the torus.
+    fn neighbors_within_periodic(&self, center: Vec2, radius: f32, bounds: Bounds, found: &mut Vec<usize>) {
+        // Query again from the images of `center` on the far side of each
+        // edge the circle crosses
+        let shifts = |center: f32, origin: f32, size: f32| {
+            [
+                Some(0.0),
+                (center - radius <= origin).then_some(size),
+                (center + radius >= origin + size).then_some(-size),
+            ]
+        };
+
+        let start = found.len();
+        for dx in shifts(center.x, bounds.x, bounds.width).into_iter().flatten() {
+            for dy in shifts(center.y, bounds.y, bounds.height).into_iter().flatten() {
+                self.neighbors_within(Vec2::new(center.x + dx, center.y + dy), radius, found);
+            }
+        }
+
+        // Above half the world size, one particle can be near several images
+        if 2.0 * radius >= bounds.width.min(bounds.height) {
+            let mut images = found.split_off(start);
+            images.sort_unstable();
+            images.dedup();
+            found.extend(images);
+        }
+    }
+}
+
+/// Which `SpatialIndex` a world uses. Saved with the physics parameters,
+/// since the order neighbours are visited in affects the last bits of the
+/// summed forces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SpatialIndexKind {
+    QuadTree,
+    #[default]
+    CellList,
+}
+
+impl SpatialIndexKind {
+    pub const ALL: [SpatialIndexKind; 2] = [SpatialIndexKind::QuadTree, SpatialIndexKind::CellList];
+
+    /// Creates an empty index covering `bounds`, tuned for queries of up to
+    /// `radius`. Particles outside `bounds` are still found, just less
+    /// efficiently.
+    pub fn create(self, bounds: Bounds, radius: f32) -> Box<dyn SpatialIndex> {
+        match self {
+            SpatialIndexKind::QuadTree => Box::new(QuadTree::new(bounds)),
+            SpatialIndexKind::CellList => Box::new(CellList::new(bounds, radius)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpatialIndexKind::QuadTree => "quadtree",
+            SpatialIndexKind::CellList => "cells",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+impl SpatialIndex for QuadTree {
+    fn build(&mut self, positions: &[Vec2]) {
+        self.clear();
+        for (i, &position) in positions.iter().enumerate() {
+            self.insert(i, position);
+        }
+    }
+
+    fn neighbors_within(&self, center: Vec2, radius: f32, found: &mut Vec<usize>) {
+        self.query_radius(center, radius, found);
+    }
+}
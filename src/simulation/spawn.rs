@@ -0,0 +1,708 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use std::path::{Path, PathBuf};
+
+use super::preset::PresetError;
+use crate::particle::{Particle, SpeciesId, SpeciesRegistry};
+use crate::utils::math::Vec2;
+
+// Candidates tried around each point before Poisson-disc sampling gives up
+// on it (Bridson's k)
+const POISSON_ATTEMPTS: usize = 30;
+/// Upper bound on the Poisson-disc lookup grid (about two cells per point),
+/// so a tiny min distance is rejected instead of exhausting memory.
+pub const MAX_POISSON_CELLS: usize = 1 << 22;
+/// Upper bound on the particles the spawns of one preset ask for together.
+/// Poisson-disc spawns without a max count are bounded by their lookup grid
+/// instead.
+pub const MAX_SPAWN_PARTICLES: usize = 1 << 20;
+/// Upper bound on the pixels of an image mask, so a corrupt header is
+/// rejected instead of exhausting memory.
+pub const MAX_MASK_PIXELS: usize = 1 << 24;
+
+/// One group of particles: where they go, which species they are and how
+/// they move. Presets combine any number of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spawn {
+    pub shape: Shape,
+    pub species: SpeciesDistribution,
+    #[serde(default)]
+    pub velocity: VelocityField,
+    /// Base mass and radius, scaled by the species multipliers.
+    #[serde(default = "default_mass")]
+    pub mass: f32,
+    #[serde(default = "default_radius")]
+    pub radius: f32,
+}
+
+fn default_mass() -> f32 {
+    1.0
+}
+
+fn default_radius() -> f32 {
+    2.0
+}
+
+fn default_threshold() -> f32 {
+    0.5
+}
+
+fn default_arms() -> usize {
+    1
+}
+
+/// Where the particles of a `Spawn` are placed. Centres and regions are
+/// fractions of the world size; radii and distances are in world units.
+/// Positions that fall outside the world are clamped onto its edge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Shape {
+    /// `count` particles uniformly at random in a rectangle.
+    Rect {
+        count: usize,
+        #[serde(default)]
+        region: Region,
+    },
+    /// `count` particles uniformly at random in a disc.
+    Disc { count: usize, centre: Vec2, radius: f32 },
+    /// `count` particles uniformly at random between two circles.
+    Ring {
+        count: usize,
+        centre: Vec2,
+        inner_radius: f32,
+        outer_radius: f32,
+    },
+    /// `columns` x `rows` particles at the centres of the cells of a grid
+    /// over a rectangle, column by column.
+    Grid {
+        columns: usize,
+        rows: usize,
+        #[serde(default)]
+        region: Region,
+    },
+    /// `count` particles normally distributed around a centre.
+    Gaussian { count: usize, centre: Vec2, std_dev: f32 },
+    /// Random points in a rectangle no closer than `min_distance` to each
+    /// other, added until no more fit or there are `max_count`.
+    PoissonDisc {
+        min_distance: f32,
+        #[serde(default)]
+        max_count: Option<usize>,
+        #[serde(default)]
+        region: Region,
+    },
+    /// `count` particles `angle_step` radians apart along a circle. Steps
+    /// that don't divide a full turn wind around it several times.
+    Circle {
+        count: usize,
+        centre: Vec2,
+        radius: f32,
+        angle_step: f32,
+        #[serde(default)]
+        start_angle: f32,
+    },
+    /// `count` particles spread evenly along the arms of an Archimedean
+    /// spiral, each arm winding `turns` times from `inner_radius` out to
+    /// `outer_radius`, then moved up to `jitter` at random.
+    Spiral {
+        count: usize,
+        centre: Vec2,
+        #[serde(default = "default_arms")]
+        arms: usize,
+        turns: f32,
+        #[serde(default)]
+        inner_radius: f32,
+        outer_radius: f32,
+        #[serde(default)]
+        start_angle: f32,
+        #[serde(default)]
+        jitter: f32,
+    },
+    /// `count` particles uniformly at random over the pixels of a greyscale
+    /// PGM image at least `threshold` bright (0 to 1), or at most with
+    /// `invert`. The image is stretched over the region.
+    ImageMask {
+        count: usize,
+        path: PathBuf,
+        #[serde(default = "default_threshold")]
+        threshold: f32,
+        #[serde(default)]
+        invert: bool,
+        #[serde(default)]
+        region: Region,
+    },
+}
+
+/// Axis-aligned rectangle in fractions of the world size; the whole world
+/// by default.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Default for Region {
+    fn default() -> Self {
+        Self {
+            min: Vec2::new(0.0, 0.0),
+            max: Vec2::new(1.0, 1.0),
+        }
+    }
+}
+
+impl Region {
+    fn centre(&self) -> Vec2 {
+        Vec2::new((self.min.x + self.max.x) / 2.0, (self.min.y + self.max.y) / 2.0)
+    }
+}
+
+/// Which species each particle of a `Spawn` is, by species name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpeciesDistribution {
+    Single { species: String },
+    /// Takes the species in turn, starting over after the last.
+    Cycle { species: Vec<String> },
+    /// Picks at random in proportion to the weights.
+    Weighted { weights: Vec<SpeciesWeight> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeciesWeight {
+    pub species: String,
+    pub weight: f32,
+}
+
+/// Initial velocity of the particles of a `Spawn`. Rotational and radial
+/// fields turn around the shape's centre (the middle of its region for
+/// rectangular shapes) unless given their own, as a fraction of the world
+/// size.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VelocityField {
+    #[default]
+    Zero,
+    /// Each component uniformly at random in `-max..max`.
+    Random { max: f32 },
+    /// Rigid rotation at `angular_velocity` radians per second, positive
+    /// turning from +x towards +y.
+    Rotational {
+        angular_velocity: f32,
+        #[serde(default)]
+        centre: Option<Vec2>,
+    },
+    /// `speed` straight away from the centre, or towards it when negative.
+    Radial {
+        speed: f32,
+        #[serde(default)]
+        centre: Option<Vec2>,
+    },
+}
+
+impl Spawn {
+    /// Checks that the spawn refers to known species and has sensible sizes.
+    /// Image masks are only read when generating.
+    pub fn validate(&self, species: &SpeciesRegistry) -> Result<(), PresetError> {
+        let invalid = |reason: String| Err(PresetError::Invalid(reason));
+        self.species.resolve(species)?;
+        match self.shape.particle_count() {
+            Some(count) if count <= MAX_SPAWN_PARTICLES => {}
+            _ => return invalid(format!("spawn asks for more than {} particles", MAX_SPAWN_PARTICLES)),
+        }
+        if !positive(self.mass) || !positive(self.radius) {
+            return invalid(format!(
+                "mass and radius must be positive, got {} and {}",
+                self.mass, self.radius
+            ));
+        }
+        let lengths: &[(&str, f32)] = match &self.shape {
+            Shape::Rect { .. } | Shape::Grid { .. } => &[],
+            Shape::Disc { radius, .. } | Shape::Circle { radius, .. } => &[("radius", *radius)],
+            Shape::Ring {
+                inner_radius,
+                outer_radius,
+                ..
+            } => &[("inner radius", *inner_radius), ("outer radius", *outer_radius)],
+            Shape::Gaussian { std_dev, .. } => &[("standard deviation", *std_dev)],
+            Shape::Spiral {
+                inner_radius,
+                outer_radius,
+                jitter,
+                ..
+            } => &[("inner radius", *inner_radius), ("outer radius", *outer_radius), ("jitter", *jitter)],
+            Shape::PoissonDisc { min_distance, .. } if !positive(*min_distance) => {
+                return invalid(format!("Poisson-disc min distance must be positive, got {}", min_distance));
+            }
+            Shape::PoissonDisc { .. } => &[],
+            Shape::ImageMask { threshold, .. } if !(0.0..=1.0).contains(threshold) => {
+                return invalid(format!("image mask threshold must be between 0 and 1, got {}", threshold));
+            }
+            Shape::ImageMask { .. } => &[],
+        };
+        if let Some((name, length)) = lengths.iter().find(|(_, length)| !(length.is_finite() && *length >= 0.0)) {
+            return invalid(format!("{} must not be negative, got {}", name, length));
+        }
+        match &self.shape {
+            Shape::Ring {
+                inner_radius,
+                outer_radius,
+                ..
+            }
+            | Shape::Spiral {
+                inner_radius,
+                outer_radius,
+                ..
+            } if inner_radius > outer_radius => {
+                invalid(format!("inner radius {} beyond outer radius {}", inner_radius, outer_radius))
+            }
+            Shape::Spiral { arms: 0, .. } => invalid("spiral needs at least one arm".to_string()),
+            Shape::Rect { region, .. }
+            | Shape::Grid { region, .. }
+            | Shape::PoissonDisc { region, .. }
+            | Shape::ImageMask { region, .. }
+                if region.min.x > region.max.x || region.min.y > region.max.y =>
+            {
+                invalid(format!("region {:?} has min beyond max", region))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Appends the spawn's particles for a world of the given size.
+    pub fn generate(
+        &self,
+        species: &SpeciesRegistry,
+        rng: &mut StdRng,
+        width: f32,
+        height: f32,
+        particles: &mut Vec<Particle>,
+    ) -> Result<(), PresetError> {
+        let picker = self.species.resolve(species)?;
+        let scale = |fraction: Vec2| Vec2::new(fraction.x * width, fraction.y * height);
+        let centre = self.velocity.centre().unwrap_or_else(|| self.shape.centre());
+        let centre = scale(centre);
+        for (i, position) in self.shape.positions(rng, width, height)?.into_iter().enumerate() {
+            let position = Vec2::new(position.x.clamp(0.0, width), position.y.clamp(0.0, height));
+            let id = picker.pick(i, rng);
+            let velocity = self.velocity.sample(rng, position - centre);
+            particles.push(species.create_particle(position, velocity, id, self.mass, self.radius));
+        }
+        Ok(())
+    }
+}
+
+impl Shape {
+    /// Particles the shape asks for, or `None` if that overflows. Poisson-disc
+    /// sampling may place fewer, and asks for none without a max count.
+    pub fn particle_count(&self) -> Option<usize> {
+        match self {
+            Shape::Rect { count, .. }
+            | Shape::Disc { count, .. }
+            | Shape::Ring { count, .. }
+            | Shape::Gaussian { count, .. }
+            | Shape::Circle { count, .. }
+            | Shape::Spiral { count, .. }
+            | Shape::ImageMask { count, .. } => Some(*count),
+            Shape::Grid { columns, rows, .. } => columns.checked_mul(*rows),
+            Shape::PoissonDisc { max_count, .. } => Some(max_count.unwrap_or(0)),
+        }
+    }
+
+    // Centre as a fraction of the world size
+    fn centre(&self) -> Vec2 {
+        match self {
+            Shape::Rect { region, .. }
+            | Shape::Grid { region, .. }
+            | Shape::PoissonDisc { region, .. }
+            | Shape::ImageMask { region, .. } => region.centre(),
+            Shape::Disc { centre, .. }
+            | Shape::Ring { centre, .. }
+            | Shape::Gaussian { centre, .. }
+            | Shape::Circle { centre, .. }
+            | Shape::Spiral { centre, .. } => *centre,
+        }
+    }
+
+    fn positions(&self, rng: &mut StdRng, width: f32, height: f32) -> Result<Vec<Vec2>, PresetError> {
+        let scale = |fraction: Vec2| Vec2::new(fraction.x * width, fraction.y * height);
+        let positions = match self {
+            Shape::Rect { count, region } => {
+                let (min, max) = (scale(region.min), scale(region.max));
+                (0..*count)
+                    .map(|_| Vec2::new(sample(rng, min.x, max.x), sample(rng, min.y, max.y)))
+                    .collect()
+            }
+            Shape::Disc { count, centre, radius } => {
+                let centre = scale(*centre);
+                (0..*count).map(|_| centre + in_ring(rng, 0.0, *radius)).collect()
+            }
+            Shape::Ring {
+                count,
+                centre,
+                inner_radius,
+                outer_radius,
+            } => {
+                let centre = scale(*centre);
+                (0..*count)
+                    .map(|_| centre + in_ring(rng, *inner_radius, *outer_radius))
+                    .collect()
+            }
+            Shape::Grid { columns, rows, region } => {
+                let (min, max) = (scale(region.min), scale(region.max));
+                let cell = Vec2::new((max.x - min.x) / *columns as f32, (max.y - min.y) / *rows as f32);
+                (0..*columns)
+                    .flat_map(|x| (0..*rows).map(move |y| (x, y)))
+                    .map(|(x, y)| Vec2::new(min.x + (x as f32 + 0.5) * cell.x, min.y + (y as f32 + 0.5) * cell.y))
+                    .collect()
+            }
+            Shape::Gaussian { count, centre, std_dev } => {
+                let centre = scale(*centre);
+                (0..*count).map(|_| centre + gaussian(rng) * *std_dev).collect()
+            }
+            Shape::PoissonDisc {
+                min_distance,
+                max_count,
+                region,
+            } => poisson_disc(
+                rng,
+                scale(region.min),
+                scale(region.max),
+                *min_distance,
+                max_count.unwrap_or(usize::MAX),
+            )?,
+            Shape::Circle {
+                count,
+                centre,
+                radius,
+                angle_step,
+                start_angle,
+            } => {
+                let centre = scale(*centre);
+                (0..*count)
+                    .map(|i| centre + unit(start_angle + i as f32 * angle_step) * *radius)
+                    .collect()
+            }
+            Shape::Spiral {
+                count,
+                centre,
+                arms,
+                turns,
+                inner_radius,
+                outer_radius,
+                start_angle,
+                jitter,
+            } => {
+                let centre = scale(*centre);
+                let per_arm = count.div_ceil(*arms);
+                (0..*count)
+                    .map(|i| {
+                        let (arm, step) = (i % arms, i / arms);
+                        // 0 at the inner end of the arm, 1 at the outer
+                        let t = step as f32 / per_arm.saturating_sub(1).max(1) as f32;
+                        let angle = start_angle + arm as f32 * TAU / *arms as f32 + t * turns * TAU;
+                        let radius = inner_radius + t * (outer_radius - inner_radius);
+                        centre + unit(angle) * radius + in_ring(rng, 0.0, *jitter)
+                    })
+                    .collect()
+            }
+            Shape::ImageMask {
+                count,
+                path,
+                threshold,
+                invert,
+                region,
+            } => {
+                let mask = Mask::load(path)?;
+                let pixels: Vec<usize> = (0..mask.values.len())
+                    .filter(|&i| (mask.values[i] >= *threshold) != *invert)
+                    .collect();
+                if pixels.is_empty() && *count > 0 {
+                    return Err(PresetError::Invalid(format!(
+                        "no pixels of {} pass the threshold",
+                        path.display()
+                    )));
+                }
+                let (min, max) = (scale(region.min), scale(region.max));
+                let pixel = Vec2::new((max.x - min.x) / mask.width as f32, (max.y - min.y) / mask.height as f32);
+                (0..*count)
+                    .map(|_| {
+                        let i = pixels[rng.gen_range(0..pixels.len())];
+                        let (x, y) = (i % mask.width, i / mask.width);
+                        Vec2::new(
+                            min.x + (x as f32 + rng.gen::<f32>()) * pixel.x,
+                            min.y + (y as f32 + rng.gen::<f32>()) * pixel.y,
+                        )
+                    })
+                    .collect()
+            }
+        };
+        Ok(positions)
+    }
+
+    /// Resolves a relative image mask path against `dir`, the directory of
+    /// the preset file it came from.
+    pub(super) fn resolve_paths(&mut self, dir: &Path) {
+        if let Shape::ImageMask { path, .. } = self {
+            if path.is_relative() {
+                *path = dir.join(&*path);
+            }
+        }
+    }
+}
+
+fn positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+// Uniform in `min..max`, or `min` for an empty range
+fn sample(rng: &mut StdRng, min: f32, max: f32) -> f32 {
+    if max > min {
+        rng.gen_range(min..max)
+    } else {
+        min
+    }
+}
+
+fn unit(angle: f32) -> Vec2 {
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+// Uniform over the area between two circles around the origin
+fn in_ring(rng: &mut StdRng, inner: f32, outer: f32) -> Vec2 {
+    let radius = sample(rng, inner * inner, outer * outer).sqrt();
+    unit(rng.gen_range(0.0..TAU)) * radius
+}
+
+// Two independent standard normal components (Box-Muller)
+fn gaussian(rng: &mut StdRng) -> Vec2 {
+    let u: f32 = 1.0 - rng.gen::<f32>(); // In (0, 1], so the log is finite
+    unit(rng.gen_range(0.0..TAU)) * (-2.0 * u.ln()).sqrt()
+}
+
+// Bridson's algorithm: grow from a random point, trying candidates at
+// min_distance..2 min_distance around points that still have room
+fn poisson_disc(
+    rng: &mut StdRng,
+    min: Vec2,
+    max: Vec2,
+    min_distance: f32,
+    max_count: usize,
+) -> Result<Vec<Vec2>, PresetError> {
+    let mut points = Vec::new();
+    if max_count == 0 || max.x < min.x || max.y < min.y {
+        return Ok(points);
+    }
+    // Cells small enough to hold at most one point
+    let cell = min_distance / std::f32::consts::SQRT_2;
+    let columns = ((max.x - min.x) / cell).ceil().max(1.0);
+    let rows = ((max.y - min.y) / cell).ceil().max(1.0);
+    if columns as f64 * rows as f64 > MAX_POISSON_CELLS as f64 {
+        return Err(PresetError::Invalid(format!(
+            "Poisson-disc min distance {} is too small for a {}x{} region (at most {} grid cells)",
+            min_distance,
+            max.x - min.x,
+            max.y - min.y,
+            MAX_POISSON_CELLS
+        )));
+    }
+    let (columns, rows) = (columns as usize, rows as usize);
+    let cell_of = |point: Vec2| {
+        let x = (((point.x - min.x) / cell) as usize).min(columns - 1);
+        let y = (((point.y - min.y) / cell) as usize).min(rows - 1);
+        (x, y)
+    };
+    let mut grid: Vec<Option<usize>> = vec![None; columns * rows];
+
+    let first = Vec2::new(sample(rng, min.x, max.x), sample(rng, min.y, max.y));
+    let (x, y) = cell_of(first);
+    grid[y * columns + x] = Some(0);
+    points.push(first);
+    let mut active = vec![0];
+
+    while !active.is_empty() && points.len() < max_count {
+        let slot = rng.gen_range(0..active.len());
+        let base = points[active[slot]];
+        let mut placed = false;
+        for _ in 0..POISSON_ATTEMPTS {
+            let candidate = base + in_ring(rng, min_distance, 2.0 * min_distance);
+            if candidate.x < min.x || candidate.x > max.x || candidate.y < min.y || candidate.y > max.y {
+                continue;
+            }
+            let (cx, cy) = cell_of(candidate);
+            let crowded = (cy.saturating_sub(2)..(cy + 3).min(rows)).any(|y| {
+                (cx.saturating_sub(2)..(cx + 3).min(columns)).any(|x| {
+                    grid[y * columns + x]
+                        .is_some_and(|other| (points[other] - candidate).length_squared() < min_distance * min_distance)
+                })
+            });
+            if !crowded {
+                grid[cy * columns + cx] = Some(points.len());
+                active.push(points.len());
+                points.push(candidate);
+                placed = true;
+                break;
+            }
+        }
+        if !placed {
+            active.swap_remove(slot);
+        }
+    }
+    Ok(points)
+}
+
+// Brightness of each pixel of a greyscale image, 0 to 1, row by row from
+// the top
+struct Mask {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl Mask {
+    fn load(path: &Path) -> Result<Self, PresetError> {
+        let bytes = std::fs::read(path)?;
+        Self::parse_pgm(&bytes)
+            .map_err(|reason| PresetError::Invalid(format!("image mask {}: {}", path.display(), reason)))
+    }
+
+    // Plain (P2) or binary (P5) PGM
+    fn parse_pgm(bytes: &[u8]) -> Result<Self, String> {
+        // Header: magic, width, height and max value, separated by
+        // whitespace and comments, then a single whitespace byte
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        while fields.len() < 4 {
+            match bytes.get(pos) {
+                None => return Err("truncated header".to_string()),
+                Some(b'#') => {
+                    while bytes.get(pos).is_some_and(|&b| b != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => pos += 1,
+                Some(_) => {
+                    let start = pos;
+                    while bytes.get(pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+                        pos += 1;
+                    }
+                    fields.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+                }
+            }
+        }
+        let number = |field: &str| field.parse::<usize>().map_err(|_| format!("bad header field {:?}", field));
+        let (width, height, max_value) = (number(&fields[1])?, number(&fields[2])?, number(&fields[3])?);
+        if width == 0 || height == 0 || !(1..=65535).contains(&max_value) {
+            return Err(format!("unsupported size {}x{} or max value {}", width, height, max_value));
+        }
+        let pixels = width
+            .checked_mul(height)
+            .filter(|&pixels| pixels <= MAX_MASK_PIXELS)
+            .ok_or_else(|| format!("{}x{} is more than {} pixels", width, height, MAX_MASK_PIXELS))?;
+        let data = &bytes[(pos + 1).min(bytes.len())..];
+
+        let raw: Vec<usize> = match fields[0].as_str() {
+            "P2" => String::from_utf8_lossy(data)
+                .split_ascii_whitespace()
+                .take(pixels)
+                .map(number)
+                .collect::<Result<_, _>>()?,
+            "P5" if max_value < 256 => data.iter().take(pixels).map(|&b| b as usize).collect(),
+            "P5" => data
+                .chunks_exact(2)
+                .take(pixels)
+                .map(|pair| (pair[0] as usize) << 8 | pair[1] as usize)
+                .collect(),
+            magic => return Err(format!("not a PGM image (magic {:?})", magic)),
+        };
+        if raw.len() < pixels {
+            return Err("truncated pixel data".to_string());
+        }
+        Ok(Self {
+            width,
+            height,
+            values: raw.into_iter().map(|value| value.min(max_value) as f32 / max_value as f32).collect(),
+        })
+    }
+}
+
+// A `SpeciesDistribution` with its names looked up
+enum SpeciesPicker {
+    Cycle(Vec<SpeciesId>),
+    Weighted(Vec<(SpeciesId, f32)>, f32),
+}
+
+impl SpeciesDistribution {
+    fn resolve(&self, registry: &SpeciesRegistry) -> Result<SpeciesPicker, PresetError> {
+        let find = |name: &str| {
+            registry
+                .find(name)
+                .ok_or_else(|| PresetError::Invalid(format!("unknown species {:?}", name)))
+        };
+        match self {
+            SpeciesDistribution::Single { species } => Ok(SpeciesPicker::Cycle(vec![find(species)?])),
+            SpeciesDistribution::Cycle { species } if species.is_empty() => {
+                Err(PresetError::Invalid("species cycle is empty".to_string()))
+            }
+            SpeciesDistribution::Cycle { species } => Ok(SpeciesPicker::Cycle(
+                species.iter().map(|name| find(name)).collect::<Result<_, _>>()?,
+            )),
+            SpeciesDistribution::Weighted { weights } => {
+                let weights: Vec<(SpeciesId, f32)> = weights
+                    .iter()
+                    .map(|entry| Ok((find(&entry.species)?, entry.weight)))
+                    .collect::<Result<_, PresetError>>()?;
+                if weights.iter().any(|&(_, weight)| !(weight.is_finite() && weight >= 0.0)) {
+                    return Err(PresetError::Invalid("species weights must not be negative".to_string()));
+                }
+                let total: f32 = weights.iter().map(|&(_, weight)| weight).sum();
+                if total <= 0.0 {
+                    return Err(PresetError::Invalid("species weights add up to zero".to_string()));
+                }
+                Ok(SpeciesPicker::Weighted(weights, total))
+            }
+        }
+    }
+}
+
+impl SpeciesPicker {
+    // Species of the `index`th particle of a spawn
+    fn pick(&self, index: usize, rng: &mut StdRng) -> SpeciesId {
+        match self {
+            SpeciesPicker::Cycle(species) => species[index % species.len()],
+            SpeciesPicker::Weighted(weights, total) => {
+                let mut roll = rng.gen_range(0.0..*total);
+                for &(species, weight) in weights {
+                    if roll < weight {
+                        return species;
+                    }
+                    roll -= weight;
+                }
+                // Rounding can leave the roll just past the last weight
+                weights.iter().rev().find(|&&(_, weight)| weight > 0.0).map_or(0, |&(species, _)| species)
+            }
+        }
+    }
+}
+
+impl VelocityField {
+    fn centre(&self) -> Option<Vec2> {
+        match *self {
+            VelocityField::Rotational { centre, .. } | VelocityField::Radial { centre, .. } => centre,
+            VelocityField::Zero | VelocityField::Random { .. } => None,
+        }
+    }
+
+    // Velocity of a particle `offset` from the field's centre
+    fn sample(&self, rng: &mut StdRng, offset: Vec2) -> Vec2 {
+        match *self {
+            VelocityField::Zero => Vec2::new(0.0, 0.0),
+            VelocityField::Random { max } => Vec2::new(sample(rng, -max, max), sample(rng, -max, max)),
+            VelocityField::Rotational { angular_velocity, .. } => {
+                Vec2::new(-offset.y, offset.x) * angular_velocity
+            }
+            VelocityField::Radial { speed, .. } => offset.normalize() * speed,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+/// Speeds the GUI can run the simulation at, relative to real time.
+pub const TIME_SCALES: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+pub const MAX_SUBSTEPS: u32 = 16;
+
+// Longest frame the accumulator accepts, so a stall (window drag, breakpoint)
+// doesn't trigger a burst of catch-up steps
+const MAX_FRAME_TIME: f32 = 0.1;
+
+/// Turns variable frame times into a whole number of fixed-size physics
+/// steps, so a run doesn't depend on the frame rate.
+///
+/// Each frame of `frame_dt` seconds is split into `substeps` steps. Time left
+/// over carries to the next frame; `alpha` says how far it is into the next
+/// step, for interpolating what is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTimestep {
+    frame_dt: f32,
+    substeps: u32,
+    time_scale: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(frame_dt: f32, substeps: u32) -> Self {
+        Self {
+            frame_dt,
+            substeps: substeps.clamp(1, MAX_SUBSTEPS),
+            time_scale: 1.0,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Simulated seconds per physics step.
+    pub fn step_dt(&self) -> f32 {
+        self.frame_dt / self.substeps as f32
+    }
+
+    pub fn substeps(&self) -> u32 {
+        self.substeps
+    }
+
+    pub fn set_substeps(&mut self, substeps: u32) {
+        // Keep the fraction of a step already accumulated
+        let alpha = self.alpha();
+        self.substeps = substeps.clamp(1, MAX_SUBSTEPS);
+        self.accumulator = alpha * self.step_dt();
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Simulated seconds per real second. Scaling runs more or fewer steps
+    /// of the same size, so a run stays reproducible at any speed.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /// Adds `elapsed` real seconds and returns how many steps are now due.
+    pub fn advance(&mut self, elapsed: f32) -> u32 {
+        self.accumulator += elapsed.clamp(0.0, MAX_FRAME_TIME) * self.time_scale;
+        let step_dt = self.step_dt();
+        let steps = (self.accumulator / step_dt).floor();
+        self.accumulator -= steps * step_dt;
+        // Rounding can leave the remainder a hair outside [0, step_dt)
+        self.accumulator = self.accumulator.clamp(0.0, step_dt);
+        steps as u32
+    }
+
+    /// Fraction of the next step already elapsed, in `[0, 1]`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step_dt()).clamp(0.0, 1.0)
+    }
+
+    /// Drops any partly elapsed step.
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(1.0 / 60.0, 1)
+    }
+}
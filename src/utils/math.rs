@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
@@ -10,7 +12,11 @@ impl Vec2 {
     }
     
     pub fn length(&self) -> f32 {
-        (self.x * self.x + self.y * self.y).sqrt()
+        self.length_squared().sqrt()
+    }
+    
+    pub fn length_squared(&self) -> f32 {
+        self.x * self.x + self.y * self.y
     }
     
     pub fn normalize(&self) -> Self {
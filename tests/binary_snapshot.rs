@@ -0,0 +1,97 @@
+use particle_life_simulator::simulation::binary_snapshot::{BINARY_SNAPSHOT_VERSION, MAGIC};
+use particle_life_simulator::simulation::{SnapshotError, SnapshotReader, World, WorldSnapshot};
+use particle_life_simulator::utils::math::Vec2;
+
+// A few particles with awkward values, so exactness is actually exercised
+fn snapshot() -> WorldSnapshot {
+    let mut world = World::new_with_seed(321.5, 123.25, 4);
+    world.get_interaction_matrix_mut().set(0, 1, 0.1 + 0.2);
+    world.get_interaction_matrix_mut().set_max_radius(1, 0, Some(77.7));
+    world.get_interaction_matrix_mut().set_min_radius(2, 3, 1.0 / 3.0);
+    world.spawn_particle(Vec2::new(1.0 / 3.0, 2.0 / 7.0), Vec2::new(-1e-7, 5e4), 0, 1.0, 2.0);
+    world.spawn_particle(Vec2::new(300.1, 0.0), Vec2::new(f32::MIN_POSITIVE, -0.0), 1, 1.0, 2.0);
+    world.spawn_particle(Vec2::new(10.0, 99.9), Vec2::new(3.3, 4.4), 3, 1.0, 2.0);
+    world.snapshot()
+}
+
+fn binary(snapshot: &WorldSnapshot) -> Vec<u8> {
+    snapshot.write_binary_to(Vec::new()).unwrap()
+}
+
+fn read(bytes: &[u8]) -> Result<WorldSnapshot, SnapshotError> {
+    WorldSnapshot::read_binary_from(bytes)
+}
+
+fn bits(snapshot: &WorldSnapshot) -> Vec<u32> {
+    snapshot
+        .particles
+        .iter()
+        .flat_map(|p| [p.position.x, p.position.y, p.velocity.x, p.velocity.y, p.mass, p.radius])
+        .map(f32::to_bits)
+        .collect()
+}
+
+#[test]
+fn binary_and_json_round_trip_exactly() {
+    let original = snapshot();
+    let from_binary = read(&binary(&original)).unwrap();
+    let from_json: WorldSnapshot = serde_json::from_str(&serde_json::to_string(&original).unwrap()).unwrap();
+    assert_eq!(from_binary, original);
+    assert_eq!(from_json, from_binary);
+    assert_eq!(bits(&from_binary), bits(&original));
+    assert_eq!(bits(&from_json), bits(&original));
+
+    // JSON read back and written as binary gives the same bytes
+    assert_eq!(binary(&from_json), binary(&original));
+
+    let bytes = binary(&original);
+    let mut reader = SnapshotReader::new(bytes.as_slice()).unwrap();
+    assert_eq!(reader.particle_count(), 3);
+    assert_eq!(reader.header(), &original.header());
+    assert_eq!(reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap(), original.particles);
+}
+
+#[test]
+fn a_bad_magic_number_is_rejected() {
+    let mut bytes = binary(&snapshot());
+    assert_eq!(bytes[..8], MAGIC);
+    bytes[0] = b'X';
+    assert!(matches!(read(&bytes), Err(SnapshotError::BadMagic)));
+    assert!(matches!(read(b"{\"version\": 1}"), Err(SnapshotError::BadMagic)));
+}
+
+#[test]
+fn truncated_streams_are_reported_at_every_offset() {
+    let bytes = binary(&snapshot());
+    for length in 0..bytes.len() {
+        assert!(
+            matches!(read(&bytes[..length]), Err(SnapshotError::Truncated)),
+            "{} of {} bytes: {:?}",
+            length,
+            bytes.len(),
+            read(&bytes[..length])
+        );
+    }
+    assert!(read(&bytes).is_ok());
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    let mut bytes = binary(&snapshot());
+    for version in [0, BINARY_SNAPSHOT_VERSION + 1, u32::MAX] {
+        bytes[8..12].copy_from_slice(&version.to_le_bytes());
+        assert!(matches!(read(&bytes), Err(SnapshotError::UnsupportedVersion(v)) if v == version));
+    }
+}
+
+#[test]
+fn out_of_range_species_ids_are_rejected() {
+    let mut bytes = binary(&snapshot());
+    // The species byte of the last particle: 4 f32s in, then mass and radius follow
+    let species = bytes.len() - 9;
+    assert_eq!(bytes[species], 3);
+    bytes[species] = 4;
+    assert!(matches!(read(&bytes), Err(SnapshotError::Invalid(_))));
+    bytes[species] = 255;
+    assert!(matches!(read(&bytes), Err(SnapshotError::Invalid(_))));
+}
@@ -0,0 +1,64 @@
+use particle_life_simulator::simulation::{BoundaryMode, InteractionMatrix, World};
+use particle_life_simulator::utils::math::Vec2;
+
+const WIDTH: f32 = 400.0;
+const HEIGHT: f32 = 300.0;
+
+// Two particles on opposite sides of the vertical seam, red attracted to blue
+fn seam_world(boundary: BoundaryMode) -> World {
+    let mut world = World::new_with_seed(WIDTH, HEIGHT, 1);
+    world.get_physics_mut().boundary = boundary;
+    let mut matrix = InteractionMatrix::new(world.get_species().len());
+    matrix.set(0, 1, 1.0);
+    world.set_interaction_matrix(matrix);
+    world.spawn_particle(Vec2::new(10.0, 150.0), Vec2::new(0.0, 0.0), 0, 1.0, 3.0);
+    world.spawn_particle(Vec2::new(WIDTH - 10.0, 150.0), Vec2::new(0.0, 0.0), 1, 1.0, 3.0);
+    world
+}
+
+#[test]
+fn minimum_image_displacement() {
+    let wrap = BoundaryMode::Wrap;
+    let d = wrap.displacement(Vec2::new(10.0, 290.0), Vec2::new(390.0, 5.0), WIDTH, HEIGHT);
+    assert_eq!(d, Vec2::new(-20.0, 15.0));
+
+    let d = BoundaryMode::Reflect.displacement(Vec2::new(10.0, 290.0), Vec2::new(390.0, 5.0), WIDTH, HEIGHT);
+    assert_eq!(d, Vec2::new(380.0, -285.0));
+}
+
+#[test]
+fn forces_act_across_the_seam_only_when_wrapping() {
+    let mut walls = seam_world(BoundaryMode::Reflect);
+    assert_eq!(walls.compute_forces()[0], Vec2::new(0.0, 0.0));
+
+    // Blue is 20 units to the left of red through the seam
+    let mut torus = seam_world(BoundaryMode::Wrap);
+    let force = torus.compute_forces()[0];
+    assert!((force.x + 0.8).abs() < 1e-6 && force.y == 0.0, "{:?}", force);
+}
+
+#[test]
+fn wrapped_particles_reenter_on_the_opposite_side() {
+    let mut world = World::new_with_seed(WIDTH, HEIGHT, 1);
+    world.get_physics_mut().boundary = BoundaryMode::Wrap;
+    world.get_physics_mut().friction_half_life = None;
+    world.spawn_particle(Vec2::new(WIDTH - 1.0, 1.0), Vec2::new(100.0, -100.0), 0, 1.0, 3.0);
+
+    world.update(0.1);
+    let particle = &world.get_particles()[0];
+    assert!((particle.position.x - 9.0).abs() < 1e-3, "{:?}", particle.position);
+    assert!((particle.position.y - (HEIGHT - 9.0)).abs() < 1e-3, "{:?}", particle.position);
+    assert_eq!(particle.velocity, Vec2::new(100.0, -100.0));
+}
+
+#[test]
+fn absorbed_particles_are_removed() {
+    let mut world = World::new_with_seed(WIDTH, HEIGHT, 1);
+    world.get_physics_mut().boundary = BoundaryMode::Absorb;
+    world.spawn_particle(Vec2::new(WIDTH - 1.0, 150.0), Vec2::new(100.0, 0.0), 0, 1.0, 3.0);
+    world.spawn_particle(Vec2::new(200.0, 150.0), Vec2::new(0.0, 0.0), 1, 1.0, 3.0);
+
+    world.update(0.1);
+    assert_eq!(world.particle_count(), 1);
+    assert_eq!(world.get_particles()[0].species, 1);
+}
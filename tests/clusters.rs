@@ -0,0 +1,127 @@
+use particle_life_simulator::analysis::{find_clusters, Cluster, ClusterEvent, ClusterParams, ClusterTracker};
+use particle_life_simulator::particle::{Particle, SpeciesId};
+use particle_life_simulator::simulation::{BoundaryMode, World};
+use particle_life_simulator::utils::math::Vec2;
+
+const PARAMS: ClusterParams = ClusterParams {
+    link_distance: 6.0,
+    min_size: 3,
+};
+
+// A `side` x `side` grid of particles 5 apart starting at `corner`
+fn add_blob(world: &mut World, corner: Vec2, side: usize, species: SpeciesId, velocity: Vec2) {
+    for i in 0..side {
+        for j in 0..side {
+            let position = Vec2::new(corner.x + i as f32 * 5.0, corner.y + j as f32 * 5.0);
+            world.add_particle(Particle::new(position, velocity, species, 1.0, 2.0));
+        }
+    }
+}
+
+fn empty_world() -> World {
+    World::new_with_seed(400.0, 300.0, 1)
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn separate_groups_form_separate_clusters() {
+    let mut world = empty_world();
+    add_blob(&mut world, Vec2::new(50.0, 50.0), 4, 0, Vec2::new(0.0, 0.0));
+    add_blob(&mut world, Vec2::new(200.0, 100.0), 3, 1, Vec2::new(0.0, 0.0));
+    // Too small to count
+    add_blob(&mut world, Vec2::new(300.0, 250.0), 1, 2, Vec2::new(0.0, 0.0));
+
+    let clusters = find_clusters(&world, &PARAMS);
+    let sizes: Vec<usize> = clusters.iter().map(Cluster::size).collect();
+    assert_eq!(sizes, vec![16, 9]);
+    assert_eq!(clusters[0].species_counts[0], 16);
+    assert_eq!(clusters[1].species_counts[1], 9);
+    assert!(close(clusters[0].centroid.x, 57.5) && close(clusters[0].centroid.y, 57.5));
+    assert!(close(clusters[1].radius, (100.0f32 / 3.0).sqrt()), "{}", clusters[1].radius);
+}
+
+#[test]
+fn clusters_straddle_the_seam_of_a_wrapping_world() {
+    let mut world = empty_world();
+    world.get_physics_mut().boundary = BoundaryMode::Wrap;
+    add_blob(&mut world, Vec2::new(395.0, 100.0), 2, 0, Vec2::new(0.0, 0.0));
+    add_blob(&mut world, Vec2::new(5.0, 100.0), 2, 0, Vec2::new(0.0, 0.0));
+
+    let clusters = find_clusters(&world, &PARAMS);
+    assert_eq!(clusters.len(), 1);
+    // Halfway between x = 397.5 and x = 7.5 across the seam
+    let centroid = clusters[0].centroid;
+    assert!(close(centroid.x, 2.5) && close(centroid.y, 102.5), "{:?}", centroid);
+    assert!(clusters[0].radius < 10.0);
+}
+
+#[test]
+fn spinning_clusters_have_angular_momentum() {
+    let mut world = empty_world();
+    let centre = Vec2::new(200.0, 150.0);
+    for k in 0..12 {
+        let angle = k as f32 * std::f32::consts::TAU / 12.0;
+        let offset = Vec2::new(angle.cos(), angle.sin()) * 10.0;
+        // Counter-clockwise at unit angular speed, drifting to the right
+        let velocity = Vec2::new(-offset.y, offset.x) + Vec2::new(3.0, 0.0);
+        world.add_particle(Particle::new(centre + offset, velocity, 0, 1.0, 2.0));
+    }
+
+    let cluster = &find_clusters(&world, &PARAMS)[0];
+    assert!(close(cluster.velocity.x, 3.0) && close(cluster.velocity.y, 0.0));
+    // Σ m r² ω = 12 * 100
+    assert!((cluster.angular_momentum - 1200.0).abs() < 0.1, "{}", cluster.angular_momentum);
+}
+
+#[test]
+fn tracker_keeps_ids_and_reports_merges_splits_and_ends() {
+    let mut tracker = ClusterTracker::new(PARAMS.min_size);
+    let mut frame = |world: &World| {
+        let mut clusters = find_clusters(world, &PARAMS);
+        let events = tracker.update(&mut clusters);
+        (clusters.iter().map(|cluster| cluster.id).collect::<Vec<_>>(), events)
+    };
+
+    // Two clusters appear
+    let mut world = empty_world();
+    add_blob(&mut world, Vec2::new(50.0, 50.0), 4, 0, Vec2::new(0.0, 0.0));
+    add_blob(&mut world, Vec2::new(200.0, 50.0), 3, 1, Vec2::new(0.0, 0.0));
+    let (ids, events) = frame(&world);
+    assert_eq!(ids, vec![1, 2]);
+    assert_eq!(events, vec![ClusterEvent::Born { id: 1 }, ClusterEvent::Born { id: 2 }]);
+
+    // Unchanged: same IDs, nothing happens
+    let (ids, events) = frame(&world);
+    assert_eq!(ids, vec![1, 2]);
+    assert!(events.is_empty());
+
+    // The smaller one moves next to the larger and they merge
+    let mut merged = empty_world();
+    add_blob(&mut merged, Vec2::new(50.0, 50.0), 4, 0, Vec2::new(0.0, 0.0));
+    add_blob(&mut merged, Vec2::new(70.0, 50.0), 3, 1, Vec2::new(0.0, 0.0));
+    let (ids, events) = frame(&merged);
+    assert_eq!(ids, vec![1]);
+    assert_eq!(
+        events,
+        vec![
+            ClusterEvent::Merged { into: 1, from: vec![1, 2] },
+            ClusterEvent::Ended { id: 2, lifetime: 2 },
+        ]
+    );
+
+    // And splits apart again: the larger piece keeps the ID
+    let (ids, events) = frame(&world);
+    assert_eq!(ids, vec![1, 3]);
+    assert_eq!(events, vec![ClusterEvent::Split { from: 1, into: vec![1, 3] }]);
+
+    // Everything disperses
+    let (ids, events) = frame(&empty_world());
+    assert!(ids.is_empty());
+    assert_eq!(
+        events,
+        vec![ClusterEvent::Ended { id: 1, lifetime: 4 }, ClusterEvent::Ended { id: 3, lifetime: 1 }]
+    );
+}
@@ -0,0 +1,107 @@
+use particle_life_simulator::particle::{Particle, SpeciesId};
+use particle_life_simulator::simulation::{InteractionMatrix, World};
+use particle_life_simulator::utils::math::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// A world with no interaction forces or friction, so only collisions change
+// velocities
+fn collision_world(restitution: f32) -> World {
+    let mut world = World::new_with_seed(1000.0, 1000.0, 1);
+    let physics = world.get_physics_mut();
+    physics.restitution = restitution;
+    physics.friction_half_life = None;
+    world.set_interaction_matrix(InteractionMatrix::new(world.get_species().len()));
+    world
+}
+
+// Adds a particle with exactly this mass, bypassing the species multipliers
+fn add(world: &mut World, position: Vec2, velocity: Vec2, species: SpeciesId, mass: f32, radius: f32) {
+    world.add_particle(Particle::new(position, velocity, species, mass, radius));
+}
+
+fn momentum(world: &World) -> Vec2 {
+    world
+        .get_particles()
+        .iter()
+        .fold(Vec2::new(0.0, 0.0), |sum, particle| sum + particle.velocity * particle.mass)
+}
+
+fn centre_of_mass(world: &World) -> Vec2 {
+    let particles = world.get_particles();
+    let mass: f32 = particles.iter().map(|particle| particle.mass).sum();
+    particles
+        .iter()
+        .fold(Vec2::new(0.0, 0.0), |sum, particle| sum + particle.position * (particle.mass / mass))
+}
+
+fn assert_close(actual: Vec2, expected: Vec2, tolerance: f32) {
+    assert!((actual - expected).length() <= tolerance, "{:?} != {:?}", actual, expected);
+}
+
+#[test]
+fn elastic_head_on_collision_matches_the_textbook_result() {
+    let mut world = collision_world(1.0);
+    add(&mut world, Vec2::new(500.0, 500.0), Vec2::new(2.0, 0.0), 0, 1.0, 3.0);
+    add(&mut world, Vec2::new(505.0, 500.0), Vec2::new(-1.0, 0.0), 1, 3.0, 3.0);
+    let before = momentum(&world);
+
+    world.update(0.001);
+    let particles = world.get_particles();
+    // Each pair is resolved once, not once from each side
+    assert_close(particles[0].velocity, Vec2::new(-2.5, 0.0), 1e-5);
+    assert_close(particles[1].velocity, Vec2::new(0.5, 0.0), 1e-5);
+    assert_close(momentum(&world), before, 1e-5);
+}
+
+#[test]
+fn inelastic_collision_stops_the_approach() {
+    let mut world = collision_world(0.0);
+    add(&mut world, Vec2::new(500.0, 500.0), Vec2::new(3.0, 1.0), 0, 2.0, 3.0);
+    add(&mut world, Vec2::new(505.0, 500.0), Vec2::new(-1.0, -2.0), 1, 1.0, 3.0);
+    let before = momentum(&world);
+
+    world.update(0.001);
+    let particles = world.get_particles();
+    // Along the normal both move together; tangential velocities are untouched
+    assert!((particles[0].velocity.x - particles[1].velocity.x).abs() < 1e-5);
+    assert_eq!(particles[0].velocity.y, 1.0);
+    assert_eq!(particles[1].velocity.y, -2.0);
+    assert_close(momentum(&world), before, 1e-5);
+}
+
+#[test]
+fn separating_pairs_are_only_pushed_apart() {
+    let mut world = collision_world(1.0);
+    add(&mut world, Vec2::new(500.0, 500.0), Vec2::new(0.0, 0.0), 0, 1.0, 3.0);
+    add(&mut world, Vec2::new(503.0, 504.0), Vec2::new(0.0, 0.0), 1, 4.0, 3.0);
+    let centre = centre_of_mass(&world);
+
+    world.update(0.001);
+    let particles = world.get_particles();
+    assert_eq!(particles[0].velocity, Vec2::new(0.0, 0.0));
+    assert_eq!(particles[1].velocity, Vec2::new(0.0, 0.0));
+    let distance = (particles[1].position - particles[0].position).length();
+    assert!((distance - 6.0).abs() < 1e-4, "{}", distance);
+    assert_close(centre_of_mass(&world), centre, 1e-4);
+}
+
+#[test]
+fn crowded_collisions_conserve_momentum_deterministically() {
+    let run = || {
+        let mut world = collision_world(0.6);
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..300 {
+            let position = Vec2::new(rng.gen_range(450.0..550.0), rng.gen_range(450.0..550.0));
+            let velocity = Vec2::new(rng.gen_range(-20.0..20.0), rng.gen_range(-20.0..20.0));
+            add(&mut world, position, velocity, rng.gen_range(0..4), rng.gen_range(0.5..2.0), 4.0);
+        }
+        let before = momentum(&world);
+        for _ in 0..20 {
+            world.update(0.01);
+            assert_close(momentum(&world), before, 1e-2);
+        }
+        world.snapshot()
+    };
+    assert_eq!(run(), run());
+}
@@ -0,0 +1,55 @@
+use particle_life_simulator::simulation::World;
+use particle_life_simulator::utils::math::Vec2;
+use rand::Rng;
+
+const STEPS: usize = 60;
+
+// Loads a randomly laid out preset, spawns from the world RNG the way the
+// GUI does and runs the simulation, returning the bits of every particle
+fn run(seed: u64, preset: u32) -> Vec<u32> {
+    let mut world = World::new_with_seed(600.0, 400.0, seed);
+    world.load_preset(preset).unwrap();
+    for step in 0..STEPS {
+        if step % 20 == 0 {
+            let rng = world.rng_mut();
+            let position = Vec2::new(rng.gen_range(0.0..600.0), rng.gen_range(0.0..400.0));
+            let velocity = Vec2::new(rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0));
+            world.spawn_particle(position, velocity, 2, 1.0, 3.0);
+        }
+        world.update(1.0 / 60.0);
+    }
+    world
+        .get_particles()
+        .iter()
+        .flat_map(|p| {
+            [p.position.x, p.position.y, p.velocity.x, p.velocity.y, p.mass, p.radius]
+                .map(f32::to_bits)
+                .into_iter()
+                .chain([p.species as u32])
+        })
+        .collect()
+}
+
+#[test]
+fn the_same_seed_gives_bit_identical_particles() {
+    for preset in [3, 6] {
+        assert_eq!(run(1234, preset), run(1234, preset), "preset {}", preset);
+    }
+}
+
+#[test]
+fn different_seeds_diverge() {
+    for preset in [3, 6] {
+        assert_ne!(run(1234, preset), run(1235, preset), "preset {}", preset);
+    }
+}
+
+#[test]
+fn reseeding_restarts_the_random_sequence() {
+    let mut world = World::new_with_seed(100.0, 100.0, 7);
+    assert_eq!(world.seed(), 7);
+    let first: [u64; 4] = std::array::from_fn(|_| world.rng_mut().gen());
+    world.reseed(7);
+    let second: [u64; 4] = std::array::from_fn(|_| world.rng_mut().gen());
+    assert_eq!(first, second);
+}
@@ -0,0 +1,84 @@
+use particle_life_simulator::particle::Particle;
+use particle_life_simulator::simulation::diagnostics::SPEED_HISTOGRAM_BINS;
+use particle_life_simulator::simulation::{Diagnostics, World};
+use particle_life_simulator::utils::math::Vec2;
+
+fn world_with(particles: &[(Vec2, Vec2, u8, f32)]) -> World {
+    let mut world = World::new_with_seed(400.0, 400.0, 1);
+    for &(position, velocity, species, mass) in particles {
+        world.add_particle(Particle::new(position, velocity, species, mass, 3.0));
+    }
+    world
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn empty_world_has_zero_diagnostics() {
+    let diagnostics = World::new_with_seed(400.0, 400.0, 1).diagnostics();
+    assert_eq!(diagnostics.particle_count, 0);
+    assert_eq!(diagnostics.kinetic_energy, 0.0);
+    assert_eq!(diagnostics.temperature, 0.0);
+    assert_eq!(diagnostics.centre_of_mass, Vec2::new(0.0, 0.0));
+    assert!(diagnostics.species.iter().all(|species| species.count == 0));
+}
+
+#[test]
+fn totals_and_means_match_hand_computed_values() {
+    let world = world_with(&[
+        (Vec2::new(0.0, 0.0), Vec2::new(3.0, 4.0), 0, 1.0),
+        (Vec2::new(30.0, 0.0), Vec2::new(0.0, -1.0), 1, 2.0),
+    ]);
+    let diagnostics = world.diagnostics();
+    assert_eq!(diagnostics.particle_count, 2);
+    assert!(close(diagnostics.kinetic_energy, 12.5 + 1.0));
+    assert_eq!(diagnostics.momentum, Vec2::new(3.0, 2.0));
+    assert!(close(diagnostics.mean_speed, 3.0));
+    assert!(close(diagnostics.centre_of_mass.x, 20.0) && close(diagnostics.centre_of_mass.y, 0.0));
+    assert_eq!(diagnostics.species[0].count, 1);
+    assert_eq!(diagnostics.species[1].count, 1);
+    assert_eq!(diagnostics.species[1].mean_velocity, Vec2::new(0.0, -1.0));
+}
+
+#[test]
+fn temperature_ignores_bulk_motion() {
+    let drifting = world_with(&[
+        (Vec2::new(10.0, 10.0), Vec2::new(5.0, 5.0), 0, 1.0),
+        (Vec2::new(50.0, 10.0), Vec2::new(5.0, 5.0), 0, 2.0),
+    ]);
+    assert!(close(drifting.diagnostics().temperature, 0.0));
+
+    // Equal and opposite: all the kinetic energy is thermal
+    let colliding = world_with(&[
+        (Vec2::new(10.0, 10.0), Vec2::new(2.0, 0.0), 0, 1.0),
+        (Vec2::new(50.0, 10.0), Vec2::new(-2.0, 0.0), 0, 1.0),
+    ]);
+    let diagnostics = colliding.diagnostics();
+    assert!(close(diagnostics.temperature, diagnostics.kinetic_energy / 2.0));
+}
+
+#[test]
+fn speed_histograms_cover_every_particle() {
+    let particles: Vec<_> = (0..40)
+        .map(|i| (Vec2::new(i as f32 * 5.0, 100.0), Vec2::new(i as f32, 0.0), (i % 3) as u8, 1.0))
+        .collect();
+    let diagnostics = world_with(&particles).diagnostics();
+    assert!(close(diagnostics.speed_bin_width, 39.0 / SPEED_HISTOGRAM_BINS as f32));
+    for species in &diagnostics.species {
+        assert_eq!(species.speed_histogram.len(), SPEED_HISTOGRAM_BINS);
+        assert_eq!(species.speed_histogram.iter().sum::<usize>(), species.count);
+    }
+    // The fastest particle lands in the last bin rather than past it
+    assert_eq!(diagnostics.species[0].speed_histogram[SPEED_HISTOGRAM_BINS - 1], 1);
+    assert!(diagnostics.species[0].speed_std_dev > 0.0);
+}
+
+#[test]
+fn diagnostics_round_trip_through_json() {
+    let world = world_with(&[(Vec2::new(1.0, 2.0), Vec2::new(3.0, 4.0), 2, 1.5)]);
+    let diagnostics = world.diagnostics();
+    let json = serde_json::to_string(&diagnostics).unwrap();
+    assert_eq!(serde_json::from_str::<Diagnostics>(&json).unwrap(), diagnostics);
+}
@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use particle_life_simulator::simulation::{World, WorldSnapshot};
+
+fn headless(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_particle-life-headless"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("particle-life-headless-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn bad_arguments_are_reported_with_usage() {
+    let cases: [(&[&str], &str); 10] = [
+        (&["--bogus"], "unknown argument --bogus"),
+        (&["--steps"], "--steps needs a value"),
+        (&["--steps", "ten"], "invalid value \"ten\" for --steps"),
+        (&["--dt", "fast"], "invalid value \"fast\" for --dt"),
+        (&["--width", "-5"], "invalid value \"-5\" for --width: must be a positive number"),
+        (&["--height", "0"], "invalid value \"0\" for --height: must be a positive number"),
+        (&["--width", "inf"], "invalid value \"inf\" for --width: must be a positive number"),
+        (&["--boundary", "bounce"], "unknown boundary mode bounce"),
+        (&["--integrator", "leapfrog"], "unknown integrator leapfrog"),
+        (&["--snapshot-format", "xml"], "unknown snapshot format xml"),
+    ];
+    for (args, message) in cases {
+        let output = headless(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(stderr(&output).contains(message), "{:?}: {}", args, stderr(&output));
+        assert!(stderr(&output).contains("Usage: particle-life-headless"));
+    }
+
+    let help = headless(&["--help"]);
+    assert!(help.status.success());
+    assert!(String::from_utf8_lossy(&help.stdout).starts_with("Usage: particle-life-headless"));
+}
+
+#[test]
+fn runs_write_stats_and_snapshots() {
+    let dir = temp_dir("run");
+    let output = headless(&[
+        "--preset", "3", "--seed", "5", "--width", "300", "--height", "200", "--steps", "4",
+        "--stats", "-", "--stats-every", "2", "--snapshot-every", "2", "--snapshot-format", "bin",
+        "--output-dir", dir.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).starts_with("Seed 5,"));
+
+    // Header and the rows for steps 0, 2 and 4
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", stdout);
+    assert!(lines[0].starts_with("step,time,particles,kinetic_energy"));
+    let steps: Vec<&str> = lines[1..].iter().map(|line| line.split(',').next().unwrap()).collect();
+    assert_eq!(steps, ["0", "2", "4"]);
+    let columns = lines[0].split(',').count();
+    assert!(lines[1..].iter().all(|line| line.split(',').count() == columns));
+
+    // The snapshots match the same run done through the library
+    let mut world = World::new_with_seed(300.0, 200.0, 5);
+    world.load_preset(3).unwrap();
+    for step in 1..=4 {
+        world.update(0.016);
+        if step % 2 == 0 {
+            let path = dir.join(format!("step_{:08}.bin", step));
+            assert_eq!(WorldSnapshot::read(&path).unwrap(), world.snapshot(), "step {}", step);
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn json_lines_stats_and_final_snapshots() {
+    let dir = temp_dir("jsonl");
+    let output = headless(&[
+        "--seed", "9", "--steps", "3", "--stats", "-", "--stats-format", "jsonl",
+        "--output-dir", dir.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<serde_json::Value> = stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[3]["step"], 3);
+    assert!(lines[3]["species"].is_array());
+
+    // Only the final state, as JSON
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(files, ["step_00000003.json"]);
+    assert!(World::from_snapshot(WorldSnapshot::read(dir.join("step_00000003.json")).unwrap()).is_ok());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unknown_presets_fail_the_run() {
+    let dir = temp_dir("unknown-preset");
+    let output = headless(&["--preset", "99", "--steps", "1", "--output-dir", dir.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("no built-in preset 99"), "{}", stderr(&output));
+    assert!(!dir.exists());
+}
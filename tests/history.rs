@@ -0,0 +1,165 @@
+use particle_life_simulator::simulation::{BoundaryMode, Command, History, InteractionChange, World};
+use particle_life_simulator::utils::math::Vec2;
+
+fn world() -> World {
+    let mut world = World::new_with_seed(400.0, 300.0, 3);
+    world.load_preset(1).unwrap();
+    world
+}
+
+fn change(source: usize, target: usize, before: f32, after: f32) -> Command {
+    Command::SetInteractions(vec![InteractionChange {
+        source,
+        target,
+        before,
+        after,
+    }])
+}
+
+#[test]
+fn matrix_and_physics_edits_undo_and_redo() {
+    let mut world = world();
+    let mut history = History::default();
+    let original = world.snapshot();
+
+    let before = world.get_interaction_matrix().get(0, 1);
+    world.get_interaction_matrix_mut().set(0, 1, 0.9);
+    history.push(change(0, 1, before, 0.9));
+    let physics = *world.get_physics();
+    world.get_physics_mut().boundary = BoundaryMode::Wrap;
+    history.push(Command::SetPhysics {
+        before: physics,
+        after: *world.get_physics(),
+    });
+    let edited = world.snapshot();
+
+    assert!(matches!(history.undo(&mut world), Ok(Some(Command::SetPhysics { .. }))));
+    assert!(history.undo(&mut world).unwrap().is_some());
+    assert_eq!(history.undo(&mut world).unwrap(), None);
+    assert_eq!(world.snapshot(), original);
+
+    history.redo(&mut world).unwrap();
+    history.redo(&mut world).unwrap();
+    assert_eq!(history.redo(&mut world).unwrap(), None);
+    assert_eq!(world.snapshot(), edited);
+
+    // A new edit after undoing discards what could have been redone
+    history.undo(&mut world).unwrap();
+    history.push(change(1, 0, 0.0, 0.5));
+    assert_eq!((history.undo_len(), history.redo_len()), (2, 0));
+}
+
+#[test]
+fn spawns_and_deletions_restore_particle_order() {
+    let mut world = world();
+    let mut history = History::default();
+    let original = world.get_particles().to_vec();
+
+    world.spawn_particle(Vec2::new(10.0, 10.0), Vec2::new(0.0, 0.0), 0, 1.0, 3.0);
+    history.push(Command::SpawnParticles {
+        first: original.len(),
+        particles: vec![world.get_particles().last().unwrap().clone()],
+    });
+    let spawned = world.get_particles().to_vec();
+
+    // Removed back to front, stored front to back
+    let mut removed: Vec<_> = [7, 3, 0].iter().map(|&index| (index, world.remove_particle(index))).collect();
+    removed.reverse();
+    history.push(Command::RemoveParticles(removed));
+    assert_eq!(world.particle_count(), original.len() - 2);
+
+    history.undo(&mut world).unwrap();
+    assert_eq!(world.get_particles(), spawned.as_slice());
+    history.undo(&mut world).unwrap();
+    assert_eq!(world.get_particles(), original.as_slice());
+    history.redo(&mut world).unwrap();
+    history.redo(&mut world).unwrap();
+    assert_eq!(world.particle_count(), original.len() - 2);
+    assert_eq!(world.get_particles()[0], spawned[1]);
+}
+
+#[test]
+fn preset_loads_are_undone_from_snapshots() {
+    let mut world = world();
+    let mut history = History::default();
+    world.get_interaction_matrix_mut().set(0, 0, 0.7);
+    for _ in 0..10 {
+        world.update(0.016);
+    }
+    let tuned = world.snapshot();
+
+    world.load_preset(2).unwrap();
+    history.push(Command::ReplaceState {
+        before: Box::new(tuned.clone()),
+        after: Box::new(world.snapshot()),
+    });
+    let loaded = world.snapshot();
+
+    history.undo(&mut world).unwrap();
+    assert_eq!(world.snapshot(), tuned);
+    history.redo(&mut world).unwrap();
+    assert_eq!(world.snapshot(), loaded);
+}
+
+#[test]
+fn drags_merge_and_the_budget_drops_the_oldest_edits() {
+    let mut world = world();
+    let mut history = History::default();
+    let before = world.get_interaction_matrix().get(2, 3);
+    for after in [0.1, 0.2, 0.3] {
+        world.get_interaction_matrix_mut().set(2, 3, after);
+        history.push_or_merge(change(2, 3, before, after));
+    }
+    assert_eq!(history.undo_len(), 1);
+    history.undo(&mut world).unwrap();
+    assert_eq!(world.get_interaction_matrix().get(2, 3), before);
+
+    let snapshot = Box::new(world.snapshot());
+    let replace = Command::ReplaceState {
+        before: snapshot.clone(),
+        after: snapshot,
+    };
+    let mut history = History::new(replace.memory_size() * 2);
+    for _ in 0..5 {
+        history.push(replace.clone());
+    }
+    assert_eq!(history.undo_len(), 2);
+    assert!(history.memory_used() <= history.budget());
+
+    // A command larger than the whole budget is not kept
+    let mut history = History::new(replace.memory_size() - 1);
+    history.push(replace);
+    assert_eq!((history.undo_len(), history.memory_used()), (0, 0));
+}
+
+#[test]
+fn absorbed_particles_drop_spawns_and_deletions() {
+    let mut world = World::new_with_seed(400.0, 300.0, 3);
+    world.get_physics_mut().boundary = BoundaryMode::Absorb;
+    let mut history = History::default();
+    // One particle about to leave through the left wall, one staying put
+    world.spawn_particle(Vec2::new(1.0, 150.0), Vec2::new(-500.0, 0.0), 0, 1.0, 3.0);
+    world.spawn_particle(Vec2::new(200.0, 150.0), Vec2::new(0.0, 0.0), 1, 1.0, 3.0);
+    world.spawn_particle(Vec2::new(300.0, 150.0), Vec2::new(0.0, 0.0), 1, 1.0, 3.0);
+    history.push(Command::SpawnParticles {
+        first: 2,
+        particles: vec![world.get_particles()[2].clone()],
+    });
+    let removed = world.remove_particle(1);
+    history.push(Command::RemoveParticles(vec![(1, removed)]));
+    history.push(change(0, 1, 0.0, 0.5));
+    history.undo(&mut world).unwrap();
+
+    world.update(0.05);
+    assert_eq!(world.particle_count(), 1);
+    assert_eq!(history.forget_particle_edits(), 2);
+    assert_eq!((history.undo_len(), history.redo_len()), (0, 1));
+    assert_eq!(history.memory_used(), change(0, 1, 0.0, 0.5).memory_size());
+
+    // Only the index-free edit is left, and the surviving particle stays
+    let survivor = world.get_particles()[0].clone();
+    history.redo(&mut world).unwrap();
+    assert_eq!(history.undo(&mut world).unwrap().map(Command::describe), Some("matrix edit".to_string()));
+    assert_eq!(history.undo(&mut world).unwrap(), None);
+    assert_eq!(world.get_particles(), [survivor]);
+}
@@ -0,0 +1,118 @@
+use particle_life_simulator::simulation::integration::compare_integrators;
+use particle_life_simulator::simulation::kernel::{InverseSquare, LennardJones, Linear, Piecewise};
+use particle_life_simulator::simulation::{
+    BoundaryMode, ForceKernel, Integrator, InteractionMatrix, Kernel, PhysicsParams, World,
+};
+use particle_life_simulator::utils::math::Vec2;
+
+// Two particles orbiting each other under a symmetric attraction, far enough
+// apart that they never collide
+fn orbit_world() -> World {
+    let mut world = World::new_with_seed(400.0, 400.0, 1);
+    let physics = world.get_physics_mut();
+    physics.boundary = BoundaryMode::Wrap;
+    physics.friction_half_life = None;
+    let mut matrix = InteractionMatrix::new(world.get_species().len());
+    matrix.set(0, 1, 1.0);
+    matrix.set(1, 0, 1.0);
+    world.set_interaction_matrix(matrix);
+    world.spawn_particle(Vec2::new(170.0, 200.0), Vec2::new(0.0, 2.0), 0, 1.0, 1.0);
+    world.spawn_particle(Vec2::new(230.0, 200.0), Vec2::new(0.0, -2.0), 1, 1.0, 1.0);
+    world
+}
+
+#[test]
+fn kernel_potentials_are_antiderivatives_of_the_forces() {
+    let kernels = [
+        Kernel::Linear(Linear),
+        Kernel::Piecewise(Piecewise::default()),
+        Kernel::InverseSquare(InverseSquare::default()),
+        Kernel::LennardJones(LennardJones::default()),
+    ];
+    let radius = 100.0;
+    for kernel in kernels {
+        assert!(kernel.potential(0.7, radius, radius).abs() < 1e-4, "{}", kernel.name());
+        // Central differences, away from the kinks of the piecewise kernel
+        for distance in [5.0, 12.0, 21.0, 47.0, 80.0, 96.0] {
+            let h = 0.01;
+            let slope = (kernel.potential(0.7, distance + h, radius) - kernel.potential(0.7, distance - h, radius)) / (2.0 * h);
+            let force = kernel.force(0.7, distance, radius);
+            assert!(
+                (slope - force).abs() < 1e-2 * force.abs().max(1.0),
+                "{} at {}: slope {} force {}",
+                kernel.name(),
+                distance,
+                slope,
+                force
+            );
+        }
+    }
+}
+
+#[test]
+fn friction_half_life_does_not_depend_on_the_step() {
+    for dt in [0.1, 0.01] {
+        let mut world = World::new_with_seed(1000.0, 1000.0, 1);
+        world.get_physics_mut().friction_half_life = Some(0.5);
+        world.spawn_particle(Vec2::new(500.0, 500.0), Vec2::new(10.0, 0.0), 0, 1.0, 1.0);
+        for _ in 0..(1.0 / dt as f64).round() as usize {
+            world.update(dt);
+        }
+        let speed = world.get_particles()[0].velocity.length();
+        assert!((speed - 2.5).abs() < 1e-3, "dt {}: speed {}", dt, speed);
+    }
+}
+
+#[test]
+fn higher_order_integrators_drift_less() {
+    let snapshot = orbit_world().snapshot();
+    let drifts = compare_integrators(&snapshot, 0.1, 500).unwrap();
+    let integrators: Vec<Integrator> = drifts.iter().map(|drift| drift.integrator).collect();
+    assert_eq!(integrators, Integrator::ALL);
+
+    let deviation = |i: usize| drifts[i].max_deviation / drifts[i].initial_energy.abs();
+    assert!(deviation(0) > deviation(1), "{:?}", drifts);
+    assert!(deviation(1) > deviation(2), "{:?}", drifts);
+    assert!(deviation(2) < 1e-3, "{:?}", drifts);
+}
+
+#[test]
+fn integrators_agree_on_small_steps() {
+    let run = |integrator| {
+        let mut world = orbit_world();
+        world.get_physics_mut().integrator = integrator;
+        for _ in 0..1000 {
+            world.update(0.005);
+        }
+        world.get_particles()[0].position
+    };
+    let reference = run(Integrator::Rk4);
+    for integrator in [Integrator::SemiImplicitEuler, Integrator::VelocityVerlet] {
+        let position = run(integrator);
+        assert!((position - reference).length() < 0.1, "{}: {:?} vs {:?}", integrator.name(), position, reference);
+    }
+}
+
+#[test]
+fn integrator_names_round_trip() {
+    for integrator in Integrator::ALL {
+        assert_eq!(Integrator::from_name(integrator.name()), Some(integrator));
+    }
+    assert_eq!(Integrator::from_name("leapfrog"), None);
+}
+
+#[test]
+fn old_physics_blocks_get_default_integration() {
+    let json = r#"{"interaction_radius": 100.0, "collision_damping": 0.8, "velocity_damping": 0.99}"#;
+    let physics: PhysicsParams = serde_json::from_str(json).unwrap();
+    assert_eq!(physics.integrator, Integrator::SemiImplicitEuler);
+    assert_eq!(physics.friction_half_life, PhysicsParams::default().friction_half_life);
+
+    let physics = PhysicsParams {
+        integrator: Integrator::Rk4,
+        friction_half_life: None,
+        ..PhysicsParams::default()
+    };
+    let json = serde_json::to_string(&physics).unwrap();
+    assert_eq!(serde_json::from_str::<PhysicsParams>(&json).unwrap(), physics);
+}
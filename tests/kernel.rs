@@ -0,0 +1,92 @@
+use particle_life_simulator::simulation::kernel::{InverseSquare, LennardJones, Linear, Piecewise};
+use particle_life_simulator::simulation::{ForceKernel, Kernel, PhysicsParams, World, WorldSnapshot};
+use particle_life_simulator::utils::math::Vec2;
+
+const RADIUS: f32 = 100.0;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-5
+}
+
+#[test]
+fn linear_fades_to_zero_at_the_radius() {
+    assert!(close(Linear.force(0.5, 0.0, RADIUS), 0.5));
+    assert!(close(Linear.force(0.5, 50.0, RADIUS), 0.25));
+    assert!(close(Linear.force(0.5, RADIUS, RADIUS), 0.0));
+}
+
+#[test]
+fn piecewise_repels_below_beta_whatever_the_strength() {
+    let kernel = Piecewise { beta: 0.3, repulsion: 2.0 };
+    for strength in [-1.0, 0.0, 1.0] {
+        assert!(close(kernel.force(strength, 0.0, RADIUS), -2.0));
+        assert!(close(kernel.force(strength, 15.0, RADIUS), -1.0));
+        assert!(kernel.force(strength, 29.9, RADIUS) < 0.0);
+    }
+}
+
+#[test]
+fn piecewise_triangle_peaks_between_beta_and_radius() {
+    let kernel = Piecewise { beta: 0.3, repulsion: 1.0 };
+    assert!(close(kernel.force(0.8, 30.0, RADIUS), 0.0));
+    assert!(close(kernel.force(0.8, 65.0, RADIUS), 0.8));
+    assert!(close(kernel.force(-0.8, 65.0, RADIUS), -0.8));
+    assert!(close(kernel.force(0.8, RADIUS, RADIUS), 0.0));
+}
+
+#[test]
+fn inverse_square_is_softened_at_contact() {
+    let kernel = InverseSquare { softening: 10.0 };
+    assert!(close(kernel.force(1.0, 0.0, RADIUS), 1.0));
+    assert!(close(kernel.force(1.0, 10.0, RADIUS), 0.5));
+    // Far away it falls off as 1/d²
+    let ratio = kernel.force(1.0, 40.0, RADIUS) / kernel.force(1.0, 80.0, RADIUS);
+    assert!((ratio - 4.0).abs() < 0.2, "{}", ratio);
+}
+
+#[test]
+fn lennard_jones_core_repels_and_tail_follows_the_matrix() {
+    let kernel = LennardJones { sigma: 10.0, epsilon: 1.0 };
+    assert!(kernel.force(1.0, 9.0, RADIUS) < 0.0);
+    assert!(kernel.force(1.0, 20.0, RADIUS) > 0.0);
+    assert!(kernel.force(-1.0, 20.0, RADIUS) < 0.0);
+    // The core is clamped instead of diverging
+    assert!(kernel.force(1.0, 0.0, RADIUS).is_finite());
+    assert_eq!(kernel.force(1.0, 0.0, RADIUS), kernel.force(1.0, 8.0, RADIUS));
+}
+
+#[test]
+fn kernels_round_trip_through_snapshots() {
+    for kernel in Kernel::all() {
+        let mut world = World::new_with_seed(400.0, 300.0, 5);
+        world.load_preset(1).unwrap();
+        world.get_physics_mut().kernel = kernel;
+        let snapshot = world.snapshot();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let from_json: WorldSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json.physics.kernel, kernel);
+
+        let bytes = snapshot.write_binary_to(Vec::new()).unwrap();
+        let from_binary = WorldSnapshot::read_binary_from(bytes.as_slice()).unwrap();
+        assert_eq!(from_binary.physics.kernel, kernel);
+    }
+}
+
+#[test]
+fn physics_without_a_kernel_defaults_to_linear() {
+    let json = r#"{"interaction_radius": 100.0, "collision_damping": 0.8, "velocity_damping": 0.99}"#;
+    let physics: PhysicsParams = serde_json::from_str(json).unwrap();
+    assert_eq!(physics.kernel, Kernel::Linear(Linear));
+}
+
+#[test]
+fn coincident_particles_feel_no_force() {
+    let mut world = World::new_with_seed(400.0, 300.0, 5);
+    world.get_physics_mut().kernel = Kernel::Piecewise(Piecewise::default());
+    world.spawn_particle(Vec2::new(100.0, 100.0), Vec2::new(0.0, 0.0), 0, 1.0, 3.0);
+    world.spawn_particle(Vec2::new(100.0, 100.0), Vec2::new(0.0, 0.0), 1, 1.0, 3.0);
+
+    let forces = world.compute_forces();
+    assert_eq!(forces, vec![Vec2::new(0.0, 0.0); 2]);
+}
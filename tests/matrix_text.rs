@@ -0,0 +1,34 @@
+use particle_life_simulator::simulation::{InteractionMatrix, MatrixDistribution};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn matrices_round_trip_through_text() {
+    let matrix = InteractionMatrix::default();
+    let text = matrix.to_text();
+    assert_eq!(text.lines().count(), 4);
+    assert_eq!(text.lines().next(), Some("-0.200 0.150 -0.100 0.300"));
+    assert_eq!(InteractionMatrix::from_text(&text).unwrap(), matrix);
+
+    // Three decimals survive the trip to within rounding
+    let random = InteractionMatrix::random(7, &mut StdRng::seed_from_u64(1), MatrixDistribution::Uniform);
+    let parsed = InteractionMatrix::from_text(&random.to_text()).unwrap();
+    for source in 0..7 {
+        for target in 0..7 {
+            assert!((parsed.get(source, target) - random.get(source, target)).abs() <= 0.0005);
+        }
+    }
+}
+
+#[test]
+fn pasted_text_may_use_commas_and_blank_lines() {
+    let matrix = InteractionMatrix::from_text("\n 0.5, -1\n\n\t0.25,0 \n").unwrap();
+    assert_eq!(matrix, InteractionMatrix::from_rows([[0.5, -1.0], [0.25, 0.0]]));
+}
+
+#[test]
+fn malformed_text_is_rejected() {
+    for text in ["", "0.1", "0.1 0.2\n0.3", "0.1 x\n0.3 0.4", "0.1 0.2\n0.3 inf"] {
+        assert!(InteractionMatrix::from_text(text).is_err(), "{:?}", text);
+    }
+}
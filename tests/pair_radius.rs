@@ -0,0 +1,175 @@
+use particle_life_simulator::particle::SpeciesRegistry;
+use particle_life_simulator::simulation::snapshot::MIN_SNAPSHOT_VERSION;
+use particle_life_simulator::simulation::{
+    InteractionMatrix, SnapshotError, SpatialIndexKind, World, WorldSnapshot, SNAPSHOT_VERSION,
+};
+use particle_life_simulator::utils::math::Vec2;
+
+// Red at the origin side, blue `distance` to its right; both attract each other
+fn pair_world(distance: f32, matrix: InteractionMatrix) -> World {
+    let mut world = World::new_with_seed(800.0, 600.0, 1);
+    world.set_interaction_matrix(matrix);
+    world.spawn_particle(Vec2::new(200.0, 300.0), Vec2::new(0.0, 0.0), 0, 1.0, 3.0);
+    world.spawn_particle(Vec2::new(200.0 + distance, 300.0), Vec2::new(0.0, 0.0), 1, 1.0, 3.0);
+    world
+}
+
+fn mutual_attraction() -> InteractionMatrix {
+    let mut matrix = InteractionMatrix::new(SpeciesRegistry::default().len());
+    matrix.set(0, 1, 1.0);
+    matrix.set(1, 0, 1.0);
+    matrix
+}
+
+#[test]
+fn one_species_can_sense_another_from_farther_away() {
+    let mut matrix = mutual_attraction();
+    matrix.set_max_radius(0, 1, Some(200.0));
+
+    for kind in SpatialIndexKind::ALL {
+        let mut world = pair_world(150.0, matrix.clone());
+        world.get_physics_mut().spatial_index = kind;
+        assert_eq!(world.query_radius(), 200.0);
+
+        // Red reaches blue at 150; blue uses the default radius of 100
+        let forces = world.compute_forces();
+        assert!((forces[0].x - 0.25).abs() < 1e-6, "{} index: {:?}", kind.name(), forces[0]);
+        assert_eq!(forces[1], Vec2::new(0.0, 0.0));
+    }
+}
+
+#[test]
+fn each_pair_is_cut_off_at_its_own_radius() {
+    let mut matrix = mutual_attraction();
+    matrix.set_max_radius(0, 1, Some(50.0));
+    let mut world = pair_world(60.0, matrix);
+
+    let forces = world.compute_forces();
+    assert_eq!(forces[0], Vec2::new(0.0, 0.0));
+    assert!((forces[1].x + 0.4).abs() < 1e-6, "{:?}", forces[1]);
+}
+
+#[test]
+fn min_radius_repels_whatever_the_strength() {
+    let mut matrix = mutual_attraction();
+    matrix.set_min_radius(0, 1, 20.0);
+    matrix.set_max_radius(0, 1, Some(120.0));
+
+    // Inside the min radius red is pushed away from blue
+    let mut world = pair_world(10.0, matrix.clone());
+    let forces = world.compute_forces();
+    assert!((forces[0].x + 0.5).abs() < 1e-6, "{:?}", forces[0]);
+
+    // Beyond it the linear kernel runs over the 20..120 band
+    let mut world = pair_world(70.0, matrix);
+    let forces = world.compute_forces();
+    assert!((forces[0].x - 0.5).abs() < 1e-6, "{:?}", forces[0]);
+}
+
+#[test]
+fn resize_keeps_radii() {
+    let mut matrix = mutual_attraction();
+    matrix.set_max_radius(1, 0, Some(42.0));
+    matrix.set_min_radius(0, 1, 7.0);
+    matrix.resize(4);
+
+    assert_eq!(matrix.max_radius(1, 0), Some(42.0));
+    assert_eq!(matrix.min_radius(0, 1), 7.0);
+    assert_eq!(matrix.max_radius(3, 3), None);
+    assert_eq!(matrix.largest_radius(100.0), 100.0);
+    assert_eq!(matrix.largest_radius(10.0), 42.0);
+}
+
+#[test]
+fn radii_round_trip_through_snapshots() {
+    // Snapshots need a matrix row for each of the four default species
+    let mut matrix = mutual_attraction();
+    matrix.resize(4);
+    matrix.set_max_radius(0, 1, Some(150.0));
+    matrix.set_min_radius(1, 0, 12.5);
+    let snapshot = pair_world(30.0, matrix.clone()).snapshot();
+
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let from_json: WorldSnapshot = serde_json::from_str(&json).unwrap();
+    assert_eq!(from_json.interaction_matrix, matrix);
+
+    let bytes = snapshot.write_binary_to(Vec::new()).unwrap();
+    let from_binary = WorldSnapshot::read_binary_from(bytes.as_slice()).unwrap();
+    assert_eq!(from_binary.interaction_matrix, matrix);
+}
+
+#[test]
+fn matrices_without_radii_still_load() {
+    let matrix: InteractionMatrix =
+        serde_json::from_str(r#"{"species_count": 2, "values": [0.1, 0.2, 0.3, 0.4]}"#).unwrap();
+    assert_eq!(matrix.get(1, 0), 0.3);
+    assert_eq!(matrix.max_radius(1, 0), None);
+    assert_eq!(matrix.min_radius(1, 0), 0.0);
+
+    let invalid = r#"{"species_count": 2, "values": [0, 0, 0, 0], "max_radius": [null, -1, null, null]}"#;
+    assert!(serde_json::from_str::<InteractionMatrix>(invalid).is_err());
+}
+
+#[test]
+fn version_1_snapshots_load_with_default_radii() {
+    let mut world = pair_world(30.0, mutual_attraction());
+    world.get_interaction_matrix_mut().resize(4);
+    let snapshot = world.snapshot();
+    assert_eq!(snapshot.version, 2);
+
+    // A version 1 file: the same state without any radii
+    let mut json = serde_json::to_value(&snapshot).unwrap();
+    json["version"] = MIN_SNAPSHOT_VERSION.into();
+    let matrix = json["interaction_matrix"].as_object_mut().unwrap();
+    matrix.remove("max_radius");
+    matrix.remove("min_radius");
+    let path = std::env::temp_dir().join(format!("particle-life-v1-{}.json", std::process::id()));
+    std::fs::write(&path, json.to_string()).unwrap();
+    let loaded = WorldSnapshot::read_json(&path);
+
+    json["version"] = (SNAPSHOT_VERSION + 1).into();
+    std::fs::write(&path, json.to_string()).unwrap();
+    let future = WorldSnapshot::read_json(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), snapshot);
+    assert!(matches!(future, Err(SnapshotError::UnsupportedVersion(3))));
+}
+
+#[test]
+fn min_radii_must_be_below_max_radii() {
+    for (min, max) in [(50.0, 50.0), (60.0, 50.0)] {
+        let json = format!(
+            r#"{{"species_count": 2, "values": [0, 0, 0, 0], "max_radius": [null, {}, null, null], "min_radius": [0, {}, 0, 0]}}"#,
+            max, min
+        );
+        let error = serde_json::from_str::<InteractionMatrix>(&json).unwrap_err();
+        assert!(error.to_string().contains("is not below max radius"), "{}", error);
+    }
+
+    // Setting either side past the other panics rather than storing a bad pair
+    let mut matrix = mutual_attraction();
+    matrix.set_max_radius(0, 1, Some(40.0));
+    matrix.set_min_radius(0, 1, 39.0);
+    assert!(matrix.check_radii().is_ok());
+    let mut too_small = matrix.clone();
+    assert!(std::panic::catch_unwind(move || too_small.set_max_radius(0, 1, Some(39.0))).is_err());
+    let mut too_large = matrix.clone();
+    assert!(std::panic::catch_unwind(move || too_large.set_min_radius(0, 1, 40.0)).is_err());
+
+    // Snapshots with such a pair are rejected in both formats
+    matrix.resize(4);
+    let snapshot = pair_world(30.0, matrix).snapshot();
+    let mut json = serde_json::to_value(&snapshot).unwrap();
+    json["interaction_matrix"]["min_radius"][1] = 45.0.into();
+    assert!(serde_json::from_value::<WorldSnapshot>(json).is_err());
+
+    let mut bytes = snapshot.write_binary_to(Vec::new()).unwrap();
+    let min_radius = 39.0f32.to_le_bytes();
+    let at = bytes.windows(4).position(|window| window == min_radius).unwrap();
+    bytes[at..at + 4].copy_from_slice(&45.0f32.to_le_bytes());
+    assert!(matches!(
+        WorldSnapshot::read_binary_from(bytes.as_slice()),
+        Err(SnapshotError::Invalid(_))
+    ));
+}
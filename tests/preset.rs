@@ -0,0 +1,112 @@
+use particle_life_simulator::particle::species::{BLUE, RED};
+use particle_life_simulator::simulation::{BoundaryMode, InteractionMatrix, Preset, PresetError, World};
+
+const GRID_PRESET: &str = r#"{
+    "name": "Test",
+    "spawns": [
+        {
+            "shape": { "type": "grid", "columns": 4, "rows": 2, "region": { "min": { "x": 0.5, "y": 0.0 }, "max": { "x": 1.0, "y": 0.5 } } },
+            "species": { "type": "cycle", "species": ["Red", "Blue"] }
+        },
+        {
+            "shape": { "type": "circle", "count": 4, "centre": { "x": 0.25, "y": 0.5 }, "radius": 10.0, "angle_step": 1.5707964 },
+            "species": { "type": "single", "species": "Blue" },
+            "velocity": { "type": "random", "max": 1.0 },
+            "radius": 3.0
+        }
+    ]
+}"#;
+
+#[test]
+fn shipped_presets_match_the_built_in_ones() {
+    let builtin = Preset::builtin();
+    assert!(builtin.len() >= 6);
+    let found: Vec<Preset> = Preset::scan_dir("presets")
+        .unwrap()
+        .into_iter()
+        .map(|(path, preset)| preset.unwrap_or_else(|e| panic!("{}: {}", path.display(), e)))
+        .collect();
+    assert_eq!(found, builtin);
+}
+
+#[test]
+fn loading_a_preset_resets_the_matrix_and_physics() {
+    let mut world = World::new_with_seed(1200.0, 800.0, 3);
+    world.load_preset(5).unwrap();
+    world.get_physics_mut().boundary = BoundaryMode::Wrap;
+    world.set_interaction_matrix(InteractionMatrix::new(world.get_species().len()));
+
+    world.load_preset(1).unwrap();
+    assert_eq!(world.get_interaction_matrix(), &InteractionMatrix::default());
+    assert_eq!(world.get_physics().boundary, BoundaryMode::default());
+    assert_eq!(world.get_species().len(), 4);
+    assert_eq!(world.particle_count(), 200);
+
+    // An unknown number is an error and keeps the current state
+    let before = world.snapshot();
+    for preset in [0, 8, 99] {
+        assert!(matches!(world.load_preset(preset), Err(PresetError::Unknown(p)) if p == preset));
+    }
+    assert_eq!(world.snapshot(), before);
+}
+
+#[test]
+fn spawns_place_particles_relative_to_the_world_size() {
+    let preset = Preset::from_json(GRID_PRESET).unwrap();
+    let mut world = World::new_with_seed(400.0, 200.0, 1);
+    world.apply_preset(&preset).unwrap();
+    let particles = world.get_particles();
+    assert_eq!(particles.len(), 12);
+
+    // Cell centres of a 4 x 2 grid over x 200..400, y 0..100
+    assert_eq!(particles[0].position.x, 225.0);
+    assert_eq!(particles[0].position.y, 25.0);
+    assert_eq!(particles[7].position.x, 375.0);
+    assert_eq!(particles[7].position.y, 75.0);
+    let species: Vec<_> = particles[..8].iter().map(|particle| particle.species).collect();
+    assert_eq!(species, [RED, BLUE, RED, BLUE, RED, BLUE, RED, BLUE]);
+
+    // A quarter turn apart around (100, 100)
+    for (particle, (dx, dy)) in particles[8..].iter().zip([(10.0, 0.0), (0.0, 10.0), (-10.0, 0.0), (0.0, -10.0)]) {
+        assert!((particle.position.x - (100.0 + dx)).abs() < 1e-3, "{:?}", particle.position);
+        assert!((particle.position.y - (100.0 + dy)).abs() < 1e-3, "{:?}", particle.position);
+        assert_eq!(particle.species, BLUE);
+        assert!(particle.velocity.x.abs() <= 1.0 && particle.velocity.y.abs() <= 1.0);
+    }
+
+    // Same seed, same world
+    let mut again = World::new_with_seed(400.0, 200.0, 1);
+    again.apply_preset(&preset).unwrap();
+    assert_eq!(again.get_particles(), world.get_particles());
+}
+
+#[test]
+fn presets_round_trip_through_json() {
+    for preset in Preset::builtin() {
+        assert_eq!(Preset::from_json(&preset.to_json().unwrap()).unwrap(), preset);
+    }
+}
+
+#[test]
+fn invalid_presets_leave_the_world_untouched() {
+    let mut world = World::new_with_seed(400.0, 300.0, 1);
+    world.load_preset(2).unwrap();
+    let before = world.snapshot();
+
+    let mut preset = Preset::from_json(GRID_PRESET).unwrap();
+    preset.spawns[0].species = serde_json::from_str(r#"{ "type": "single", "species": "Purple" }"#).unwrap();
+    assert!(matches!(world.apply_preset(&preset), Err(PresetError::Invalid(_))));
+    assert_eq!(world.snapshot(), before);
+
+    let mut preset = Preset::from_json(GRID_PRESET).unwrap();
+    preset.interaction_matrix = InteractionMatrix::new(2);
+    assert!(matches!(world.apply_preset(&preset), Err(PresetError::Invalid(_))));
+    assert_eq!(world.snapshot(), before);
+
+    let mut preset = Preset::from_json(GRID_PRESET).unwrap();
+    preset.physics.friction_half_life = Some(-1.0);
+    assert!(matches!(world.apply_preset(&preset), Err(PresetError::Invalid(_))));
+    assert_eq!(world.snapshot(), before);
+
+    assert!(matches!(Preset::from_json("{ \"spawns\": [] }"), Err(PresetError::Json(_))));
+}
@@ -0,0 +1,117 @@
+use particle_life_simulator::simulation::{Bounds, QuadTree};
+use particle_life_simulator::utils::math::Vec2;
+use proptest::prelude::*;
+
+const WORLD: Bounds = Bounds {
+    x: 0.0,
+    y: 0.0,
+    width: 1000.0,
+    height: 600.0,
+};
+
+// Mostly points inside the world, some on its edges and a few outside
+fn point() -> impl Strategy<Value = Vec2> {
+    prop_oneof![
+        8 => (0.0f32..=1000.0, 0.0f32..=600.0).prop_map(|(x, y)| Vec2::new(x, y)),
+        1 => (prop_oneof![Just(0.0f32), Just(500.0), Just(1000.0)], 0.0f32..=600.0)
+            .prop_map(|(x, y)| Vec2::new(x, y)),
+        1 => (-200.0f32..1200.0, -200.0f32..800.0).prop_map(|(x, y)| Vec2::new(x, y)),
+    ]
+}
+
+fn points() -> impl Strategy<Value = Vec<Vec2>> {
+    prop::collection::vec(point(), 0..400)
+}
+
+fn build(points: &[Vec2], batch: bool) -> QuadTree {
+    let mut tree = QuadTree::new(WORLD);
+    if batch {
+        let entries: Vec<_> = points.iter().copied().enumerate().collect();
+        tree.batch_insert(&entries);
+    } else {
+        for (i, &point) in points.iter().enumerate() {
+            tree.insert(i, point);
+        }
+    }
+    tree
+}
+
+fn sorted(mut indices: Vec<usize>) -> Vec<usize> {
+    indices.sort_unstable();
+    indices
+}
+
+proptest! {
+    #[test]
+    fn rectangle_query_matches_brute_force(
+        points in points(),
+        batch in any::<bool>(),
+        corner in point(),
+        size in (0.0f32..600.0, 0.0f32..400.0),
+    ) {
+        let tree = build(&points, batch);
+        let range = Bounds { x: corner.x, y: corner.y, width: size.0, height: size.1 };
+
+        let mut found = Vec::new();
+        tree.query(&range, &mut found);
+
+        let expected: Vec<usize> = (0..points.len()).filter(|&i| range.contains(points[i])).collect();
+        prop_assert_eq!(sorted(found), expected);
+    }
+
+    #[test]
+    fn radius_query_matches_brute_force(
+        points in points(),
+        batch in any::<bool>(),
+        center in point(),
+        radius in 0.0f32..400.0,
+    ) {
+        let tree = build(&points, batch);
+
+        let mut found = Vec::new();
+        tree.query_radius(center, radius, &mut found);
+
+        let expected: Vec<usize> = (0..points.len())
+            .filter(|&i| (points[i] - center).length_squared() <= radius * radius)
+            .collect();
+        prop_assert_eq!(sorted(found), expected);
+    }
+
+    #[test]
+    fn every_point_is_stored_once(points in points()) {
+        let tree = build(&points, false);
+        prop_assert_eq!(tree.get_stats().total_particles, points.len());
+
+        let mut found = Vec::new();
+        tree.query(&Bounds { x: -1e6, y: -1e6, width: 2e6, height: 2e6 }, &mut found);
+        prop_assert_eq!(sorted(found), (0..points.len()).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn subdivision_spreads_points_across_children() {
+    let mut tree = QuadTree::new(WORLD);
+    for i in 0..1000 {
+        let point = Vec2::new((i % 40) as f32 * 25.0, (i / 40) as f32 * 24.0);
+        tree.insert(i, point);
+    }
+
+    let stats = tree.get_stats();
+    assert_eq!(stats.total_particles, 1000);
+    assert!(stats.leaf_nodes > 64, "only {} leaves", stats.leaf_nodes);
+    assert!(stats.avg_particles_per_leaf <= 10.0);
+}
+
+#[test]
+fn clear_forgets_everything() {
+    let mut tree = QuadTree::new(WORLD);
+    for i in 0..100 {
+        tree.insert(i, Vec2::new(i as f32 * 10.0, 300.0));
+    }
+    tree.insert(100, Vec2::new(-5.0, -5.0));
+    tree.clear();
+
+    let stats = tree.get_stats();
+    assert_eq!(stats.total_particles, 0);
+    assert_eq!(stats.total_nodes, 1);
+}
@@ -0,0 +1,78 @@
+use particle_life_simulator::simulation::interaction_matrix::{SELF_REPULSION, SPARSE_DENSITY};
+use particle_life_simulator::simulation::recording::RecordedEvent;
+use particle_life_simulator::simulation::{InteractionMatrix, MatrixDistribution, World, MAX_SPECIES};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn random(seed: u64, distribution: MatrixDistribution) -> InteractionMatrix {
+    InteractionMatrix::random(MAX_SPECIES, &mut StdRng::seed_from_u64(seed), distribution)
+}
+
+fn pairs(matrix: &InteractionMatrix) -> impl Iterator<Item = (usize, usize)> {
+    let n = matrix.species_count();
+    (0..n).flat_map(move |source| (0..n).map(move |target| (source, target)))
+}
+
+#[test]
+fn the_same_seed_gives_the_same_rules() {
+    for distribution in MatrixDistribution::ALL {
+        assert_eq!(random(42, distribution), random(42, distribution));
+        assert_ne!(random(42, distribution), random(43, distribution));
+        let matrix = random(7, distribution);
+        assert!(pairs(&matrix).all(|(a, b)| (-1.0..1.0).contains(&matrix.get(a, b))));
+    }
+}
+
+#[test]
+fn distributions_have_their_shape() {
+    let symmetric = random(1, MatrixDistribution::Symmetric);
+    assert!(pairs(&symmetric).all(|(a, b)| symmetric.get(a, b) == symmetric.get(b, a)));
+
+    let antisymmetric = random(1, MatrixDistribution::Antisymmetric);
+    assert!(pairs(&antisymmetric)
+        .filter(|(a, b)| a != b)
+        .all(|(a, b)| antisymmetric.get(a, b) == -antisymmetric.get(b, a)));
+
+    let repulsive = random(1, MatrixDistribution::DiagonalRepulsive);
+    assert!((0..MAX_SPECIES).all(|a| SELF_REPULSION.contains(&repulsive.get(a, a))));
+
+    // 256 entries per matrix, over several seeds
+    let (mut non_zero, mut total) = (0, 0);
+    for seed in 0..10 {
+        let sparse = random(seed, MatrixDistribution::Sparse);
+        non_zero += pairs(&sparse).filter(|&(a, b)| sparse.get(a, b) != 0.0).count();
+        total += MAX_SPECIES * MAX_SPECIES;
+    }
+    let density = non_zero as f32 / total as f32;
+    assert!((density - SPARSE_DENSITY).abs() < 0.05, "{}", density);
+}
+
+#[test]
+fn randomizing_keeps_radii_and_particles() {
+    let mut world = World::new_with_seed(400.0, 300.0, 2);
+    world.load_preset(1).unwrap();
+    world.get_interaction_matrix_mut().set_max_radius(0, 1, Some(40.0));
+    world.get_interaction_matrix_mut().set_min_radius(1, 0, 5.0);
+    let particles = world.get_particles().to_vec();
+
+    world
+        .get_interaction_matrix_mut()
+        .randomize(&mut StdRng::seed_from_u64(9), MatrixDistribution::Uniform);
+    let matrix = world.get_interaction_matrix();
+    assert_eq!(matrix.max_radius(0, 1), Some(40.0));
+    assert_eq!(matrix.min_radius(1, 0), 5.0);
+    assert_eq!(world.get_particles(), particles.as_slice());
+
+    // Replaying the recorded result gives the same matrix
+    let mut replayed = World::new_with_seed(400.0, 300.0, 2);
+    RecordedEvent::SetMatrix(matrix.clone()).apply(&mut replayed).unwrap();
+    assert_eq!(replayed.get_interaction_matrix(), world.get_interaction_matrix());
+}
+
+#[test]
+fn distribution_names_round_trip() {
+    for distribution in MatrixDistribution::ALL {
+        assert_eq!(MatrixDistribution::from_name(distribution.name()), Some(distribution));
+    }
+    assert_eq!(MatrixDistribution::from_name("gaussian"), None);
+}
@@ -0,0 +1,86 @@
+use particle_life_simulator::analysis::{RadialDistribution, RdfParams};
+use particle_life_simulator::particle::Particle;
+use particle_life_simulator::simulation::{BoundaryMode, World};
+use particle_life_simulator::utils::math::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const PARAMS: RdfParams = RdfParams {
+    max_radius: 60.0,
+    bins: 12,
+};
+
+// Uniformly scattered particles of species 0 and 1: an ideal gas, g(r) = 1
+fn gas(seed: u64, boundary: BoundaryMode) -> World {
+    let mut world = World::new_with_seed(400.0, 300.0, seed);
+    world.get_physics_mut().boundary = boundary;
+    let mut rng = StdRng::seed_from_u64(seed);
+    for i in 0..1500 {
+        let position = Vec2::new(rng.gen_range(0.0..400.0), rng.gen_range(0.0..300.0));
+        world.add_particle(Particle::new(position, Vec2::new(0.0, 0.0), (i % 2) as u8, 1.0, 1.0));
+    }
+    world
+}
+
+#[test]
+fn uniform_gas_has_flat_pair_correlation() {
+    for boundary in [BoundaryMode::Wrap, BoundaryMode::Reflect] {
+        let mut rdf = RadialDistribution::new(PARAMS, 2);
+        for seed in 0..10 {
+            rdf.add_sample(&gas(seed, boundary));
+        }
+        let result = rdf.result();
+        assert_eq!(result.samples, 10);
+        assert_eq!(result.pairs.len(), 3);
+        for pair in &result.pairs {
+            // The innermost shells hold few pairs, so allow more noise there
+            for (bin, &g) in pair.g.iter().enumerate().skip(2) {
+                assert!((g - 1.0).abs() < 0.1, "{:?} {}-{} bin {}: {}", boundary, pair.source, pair.target, bin, g);
+            }
+        }
+    }
+}
+
+#[test]
+fn segregated_species_do_not_correlate_at_short_range() {
+    // Species 0 on the left, 1 on the right, both on a 10-unit lattice
+    let mut world = World::new_with_seed(400.0, 300.0, 1);
+    world.get_physics_mut().boundary = BoundaryMode::Wrap;
+    for i in 0..40 {
+        for j in 0..30 {
+            let species = if i < 20 { 0 } else { 1 };
+            let position = Vec2::new(i as f32 * 10.0 + 5.0, j as f32 * 10.0 + 5.0);
+            world.add_particle(Particle::new(position, Vec2::new(0.0, 0.0), species, 1.0, 1.0));
+        }
+    }
+    let mut rdf = RadialDistribution::new(RdfParams { max_radius: 25.0, bins: 5 }, 2);
+    rdf.add_sample(&world);
+    let result = rdf.result();
+
+    // Nothing closer than the lattice spacing; a sharp peak at it
+    let like = &result.pairs[0];
+    assert_eq!((like.source, like.target), (0, 0));
+    assert_eq!(like.g[0], 0.0);
+    assert!(like.g[2] > 2.0, "{:?}", like.g);
+
+    // Unlike pairs only meet along the two seams between the halves
+    let unlike = &result.pairs[1];
+    assert_eq!((unlike.source, unlike.target), (0, 1));
+    assert!(unlike.g[2] < like.g[2] / 5.0, "{:?} vs {:?}", unlike.g, like.g);
+}
+
+#[test]
+fn reset_starts_a_new_window() {
+    let mut rdf = RadialDistribution::new(PARAMS, 2);
+    rdf.add_sample(&gas(1, BoundaryMode::Wrap));
+    rdf.reset();
+    assert_eq!(rdf.samples(), 0);
+    assert!(rdf.result().pairs.iter().all(|pair| pair.g.iter().all(|&g| g == 0.0)));
+
+    // Species with no particles give zeros rather than NaN
+    let mut rdf = RadialDistribution::new(PARAMS, 4);
+    rdf.add_sample(&gas(1, BoundaryMode::Wrap));
+    let result = rdf.result();
+    assert!(result.pairs.iter().all(|pair| pair.g.iter().all(|g| g.is_finite())));
+    assert_eq!(result.radii[0], 2.5);
+}
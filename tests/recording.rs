@@ -0,0 +1,85 @@
+use particle_life_simulator::simulation::recording::{Player, RecordedEvent, Recorder, Recording};
+use particle_life_simulator::simulation::{BoundaryMode, Integrator, World, WorldSnapshot};
+use particle_life_simulator::utils::math::Vec2;
+
+const STEPS: usize = 120;
+const DT: f32 = 1.0 / 60.0;
+
+// Runs a session with events mixed in between steps, returning the live
+// world and its recording
+fn record_session() -> (World, Recording) {
+    let mut world = World::new_with_seed(500.0, 400.0, 21);
+    world.load_preset(3).unwrap();
+    let mut recorder = Recorder::with_keyframe_interval(&world, 25);
+    for step in 0..STEPS {
+        match step {
+            10 | 55 => {
+                world.spawn_particle(Vec2::new(250.0, 200.0), Vec2::new(30.0, -12.5), 1, 1.0, 3.0);
+                let particle = world.get_particles().last().unwrap().clone();
+                recorder.record_event(RecordedEvent::SpawnParticle(particle));
+            }
+            30 => {
+                world.get_interaction_matrix_mut().set(0, 2, 0.8);
+                recorder.record_event(RecordedEvent::SetInteraction {
+                    source: 0,
+                    target: 2,
+                    value: 0.8,
+                });
+            }
+            70 => {
+                world.get_physics_mut().boundary = BoundaryMode::Wrap;
+                world.get_physics_mut().integrator = Integrator::VelocityVerlet;
+                recorder.record_event(RecordedEvent::SetPhysics(*world.get_physics()));
+            }
+            _ => {}
+        }
+        world.update(DT);
+        recorder.record_step(&world, DT);
+    }
+    (world, recorder.finish())
+}
+
+fn stepped(recording: &Recording, frames: usize) -> WorldSnapshot {
+    let mut player = Player::new(recording.clone()).unwrap();
+    for _ in 0..frames {
+        assert!(player.step().unwrap());
+    }
+    player.world().snapshot()
+}
+
+#[test]
+fn playback_reproduces_the_live_session() {
+    let (live, recording) = record_session();
+    assert_eq!(recording.frame_count(), STEPS);
+    assert_eq!(recording.keyframes.len(), STEPS / 25);
+
+    let mut player = Player::new(recording).unwrap();
+    while player.step().unwrap() {}
+    assert!(player.is_finished());
+    assert_eq!(player.world().get_particles(), live.get_particles());
+    assert_eq!(player.world().snapshot(), live.snapshot());
+}
+
+#[test]
+fn seeking_matches_stepping_from_the_start() {
+    let (_, recording) = record_session();
+    let mut player = Player::new(recording.clone()).unwrap();
+    // Forwards past keyframes, backwards, to a keyframe exactly, and to the start
+    for frame in [37, 110, 60, 50, 12, 75, 0, STEPS] {
+        player.seek(frame).unwrap();
+        assert_eq!(player.frame(), frame);
+        assert_eq!(player.world().snapshot(), stepped(&recording, frame), "frame {}", frame);
+    }
+    player.seek(STEPS + 100).unwrap();
+    assert_eq!(player.frame(), STEPS);
+}
+
+#[test]
+fn recordings_round_trip_through_files() {
+    let (_, recording) = record_session();
+    let path = std::env::temp_dir().join(format!("particle-life-recording-{}.json", std::process::id()));
+    recording.save(&path).unwrap();
+    let loaded = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, recording);
+}
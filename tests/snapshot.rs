@@ -0,0 +1,99 @@
+use particle_life_simulator::particle::{Species, SpeciesRegistry};
+use particle_life_simulator::simulation::snapshot::{SnapshotError, WorldSnapshot};
+use particle_life_simulator::simulation::kernel::InverseSquare;
+use particle_life_simulator::simulation::{
+    InteractionMatrix, Kernel, PhysicsParams, World, MAX_COLLISION_ITERATIONS, MAX_SPECIES,
+};
+
+fn world() -> World {
+    let mut world = World::new_with_seed(640.0, 480.0, 11);
+    world.load_preset(3).unwrap();
+    world.get_interaction_matrix_mut().set(1, 2, 0.42);
+    world.get_interaction_matrix_mut().set_max_radius(2, 1, Some(60.0));
+    for _ in 0..5 {
+        world.update(0.016);
+    }
+    world
+}
+
+fn assert_invalid(snapshot: WorldSnapshot) {
+    assert!(matches!(snapshot.validate(), Err(SnapshotError::Invalid(_))), "{:?}", snapshot.validate());
+}
+
+#[test]
+fn json_snapshots_restore_an_identical_world() {
+    let world = world();
+    let path = std::env::temp_dir().join(format!("particle-life-snapshot-{}.json", std::process::id()));
+    world.save_snapshot(&path).unwrap();
+
+    let mut loaded = World::new_with_seed(100.0, 100.0, 0);
+    loaded.load_snapshot(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.snapshot(), world.snapshot());
+    assert_eq!((loaded.width(), loaded.height()), (640.0, 480.0));
+
+    // A restored world steps exactly like the original
+    let mut original = world;
+    original.update(0.016);
+    loaded.update(0.016);
+    assert_eq!(loaded.get_particles(), original.get_particles());
+}
+
+#[test]
+fn matrices_smaller_than_the_species_are_rejected() {
+    let mut snapshot = world().snapshot();
+    snapshot.interaction_matrix = InteractionMatrix::new(3);
+    assert_invalid(snapshot);
+}
+
+#[test]
+fn too_many_species_are_rejected() {
+    let mut snapshot = world().snapshot();
+    let mut species = SpeciesRegistry::new();
+    for i in 0..MAX_SPECIES {
+        species.register(Species::new(&format!("S{}", i), [1.0; 4], 1.0, 1.0));
+    }
+    snapshot.interaction_matrix = InteractionMatrix::new(MAX_SPECIES);
+    snapshot.species = species.clone();
+    assert!(snapshot.validate().is_ok());
+
+    // The registry refuses a 17th species, so build the list through JSON
+    let mut json = serde_json::to_value(&species).unwrap();
+    let extra = json[0].clone();
+    json.as_array_mut().unwrap().push(extra);
+    snapshot.species = serde_json::from_value(json).unwrap();
+    assert_invalid(snapshot);
+}
+
+#[test]
+fn unknown_particle_species_are_rejected() {
+    let world = world();
+    let mut snapshot = world.snapshot();
+    snapshot.particles[3].species = world.get_species().len() as u8;
+    assert_invalid(snapshot.clone());
+
+    // The world is left untouched by a failed restore
+    let mut target = world;
+    let before = target.snapshot();
+    assert!(target.restore(snapshot).is_err());
+    assert_eq!(target.snapshot(), before);
+}
+
+#[test]
+fn out_of_range_physics_is_rejected() {
+    let snapshot = world().snapshot();
+    let edits: [fn(&mut PhysicsParams); 6] = [
+        |physics| physics.interaction_radius = -10.0,
+        |physics| physics.interaction_radius = f32::NAN,
+        |physics| physics.friction_half_life = Some(0.0),
+        |physics| physics.restitution = 1.5,
+        |physics| physics.collision_iterations = MAX_COLLISION_ITERATIONS + 1,
+        |physics| physics.kernel = Kernel::InverseSquare(InverseSquare { softening: 0.0 }),
+    ];
+    for edit in edits {
+        let mut invalid = snapshot.clone();
+        edit(&mut invalid.physics);
+        assert_invalid(invalid);
+    }
+    assert!(snapshot.validate().is_ok());
+}
@@ -0,0 +1,83 @@
+use particle_life_simulator::simulation::{BoundaryMode, Bounds, SpatialIndexKind};
+use particle_life_simulator::utils::math::Vec2;
+use proptest::prelude::*;
+
+const WORLD: Bounds = Bounds {
+    x: 0.0,
+    y: 0.0,
+    width: 1000.0,
+    height: 600.0,
+};
+
+fn point() -> impl Strategy<Value = Vec2> {
+    prop_oneof![
+        9 => (0.0f32..=1000.0, 0.0f32..=600.0).prop_map(|(x, y)| Vec2::new(x, y)),
+        1 => (-300.0f32..1300.0, -300.0f32..900.0).prop_map(|(x, y)| Vec2::new(x, y)),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn neighbors_match_brute_force(
+        positions in prop::collection::vec(point(), 0..400),
+        cell_size in 5.0f32..300.0,
+        center in point(),
+        radius in 0.0f32..400.0,
+    ) {
+        let expected: Vec<usize> = (0..positions.len())
+            .filter(|&i| (positions[i] - center).length_squared() <= radius * radius)
+            .collect();
+
+        for kind in SpatialIndexKind::ALL {
+            let mut index = kind.create(WORLD, cell_size);
+            index.build(&positions);
+
+            let mut found = Vec::new();
+            index.neighbors_within(center, radius, &mut found);
+            found.sort_unstable();
+            prop_assert_eq!(&found, &expected, "{} index", kind.name());
+        }
+    }
+}
+
+#[test]
+fn rebuilding_replaces_previous_contents() {
+    for kind in SpatialIndexKind::ALL {
+        let mut index = kind.create(WORLD, 100.0);
+        index.build(&[Vec2::new(10.0, 10.0), Vec2::new(20.0, 10.0)]);
+        index.build(&[Vec2::new(500.0, 300.0)]);
+
+        let mut found = Vec::new();
+        index.neighbors_within(Vec2::new(10.0, 10.0), 50.0, &mut found);
+        assert!(found.is_empty(), "{} index kept stale entries", kind.name());
+
+        index.neighbors_within(Vec2::new(500.0, 300.0), 0.0, &mut found);
+        assert_eq!(found, vec![0], "{} index", kind.name());
+    }
+}
+
+proptest! {
+    #[test]
+    fn periodic_neighbors_match_brute_force(
+        positions in prop::collection::vec((0.0f32..1000.0, 0.0f32..600.0).prop_map(|(x, y)| Vec2::new(x, y)), 0..300),
+        center in (0.0f32..1000.0, 0.0f32..600.0).prop_map(|(x, y)| Vec2::new(x, y)),
+        radius in 0.0f32..600.0,
+    ) {
+        let expected: Vec<usize> = (0..positions.len())
+            .filter(|&i| {
+                let d = BoundaryMode::Wrap.displacement(center, positions[i], WORLD.width, WORLD.height);
+                d.length_squared() <= radius * radius
+            })
+            .collect();
+
+        for kind in SpatialIndexKind::ALL {
+            let mut index = kind.create(WORLD, 100.0);
+            index.build(&positions);
+
+            let mut found = Vec::new();
+            index.neighbors_within_periodic(center, radius, WORLD, &mut found);
+            found.sort_unstable();
+            prop_assert_eq!(&found, &expected, "{} index", kind.name());
+        }
+    }
+}
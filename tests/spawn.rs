@@ -0,0 +1,236 @@
+use particle_life_simulator::simulation::{
+    Preset, PresetError, Region, Shape, Spawn, SpeciesDistribution, VelocityField, World, MAX_SPAWN_PARTICLES,
+};
+use particle_life_simulator::utils::math::Vec2;
+
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 600.0;
+const CENTRE: Vec2 = Vec2 { x: 0.5, y: 0.5 };
+
+fn spawn(shape: Shape, velocity: VelocityField) -> Spawn {
+    Spawn {
+        shape,
+        species: SpeciesDistribution::Single {
+            species: "Red".to_string(),
+        },
+        velocity,
+        mass: 1.0,
+        radius: 2.0,
+    }
+}
+
+fn apply(spawns: Vec<Spawn>) -> Result<World, PresetError> {
+    let preset = Preset {
+        spawns,
+        ..serde_json::from_str(r#"{ "name": "Test" }"#).unwrap()
+    };
+    let mut world = World::new_with_seed(WIDTH, HEIGHT, 7);
+    world.apply_preset(&preset)?;
+    Ok(world)
+}
+
+fn positions(shape: Shape) -> Vec<Vec2> {
+    let world = apply(vec![spawn(shape, VelocityField::Zero)]).unwrap();
+    world.get_particles().iter().map(|particle| particle.position).collect()
+}
+
+fn distance_from_centre(position: Vec2) -> f32 {
+    (position - Vec2::new(WIDTH / 2.0, HEIGHT / 2.0)).length()
+}
+
+#[test]
+fn shapes_stay_inside_their_bounds() {
+    let disc = positions(Shape::Disc {
+        count: 500,
+        centre: CENTRE,
+        radius: 100.0,
+    });
+    assert_eq!(disc.len(), 500);
+    assert!(disc.iter().all(|&p| distance_from_centre(p) <= 100.001));
+
+    let ring = positions(Shape::Ring {
+        count: 500,
+        centre: CENTRE,
+        inner_radius: 150.0,
+        outer_radius: 200.0,
+    });
+    assert!(ring.iter().all(|&p| (149.99..=200.01).contains(&distance_from_centre(p))));
+
+    let rect = positions(Shape::Rect {
+        count: 500,
+        region: Region {
+            min: Vec2::new(0.0, 0.5),
+            max: Vec2::new(0.25, 1.0),
+        },
+    });
+    assert!(rect.iter().all(|p| p.x <= 200.0 && p.y >= 300.0));
+
+    let spiral = positions(Shape::Spiral {
+        count: 300,
+        centre: CENTRE,
+        arms: 3,
+        turns: 2.0,
+        inner_radius: 20.0,
+        outer_radius: 250.0,
+        start_angle: 0.0,
+        jitter: 0.0,
+    });
+    assert_eq!(spiral.len(), 300);
+    let radii: Vec<f32> = spiral.iter().map(|&p| distance_from_centre(p)).collect();
+    assert!(radii.iter().all(|&r| (19.99..=250.01).contains(&r)));
+    // Each arm starts at the inner radius and ends at the outer
+    assert!((radii[0] - 20.0).abs() < 0.01 && (radii[299] - 250.0).abs() < 0.01);
+}
+
+#[test]
+fn gaussian_blobs_have_the_requested_spread() {
+    let blob = positions(Shape::Gaussian {
+        count: 4000,
+        centre: CENTRE,
+        std_dev: 40.0,
+    });
+    let n = blob.len() as f32;
+    let mean = blob.iter().fold(Vec2::new(0.0, 0.0), |sum, &p| sum + p) * (1.0 / n);
+    let variance = blob.iter().map(|&p| (p.x - mean.x).powi(2)).sum::<f32>() / n;
+    assert!((mean.x - 400.0).abs() < 3.0 && (mean.y - 300.0).abs() < 3.0, "{:?}", mean);
+    assert!((variance.sqrt() - 40.0).abs() < 2.0, "{}", variance.sqrt());
+}
+
+#[test]
+fn poisson_disc_points_keep_their_distance() {
+    let points = positions(Shape::PoissonDisc {
+        min_distance: 30.0,
+        max_count: None,
+        region: Region::default(),
+    });
+    // Roughly one point per 1.5 to 3 min distance squared of area
+    assert!((200..=600).contains(&points.len()), "{}", points.len());
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            assert!((*a - *b).length() >= 29.99);
+        }
+    }
+
+    let capped = positions(Shape::PoissonDisc {
+        min_distance: 30.0,
+        max_count: Some(50),
+        region: Region::default(),
+    });
+    assert_eq!(capped.len(), 50);
+}
+
+#[test]
+fn image_masks_place_particles_on_bright_pixels() {
+    // 4 x 2 image, bright in the left half
+    let path = std::env::temp_dir().join(format!("particle-life-mask-{}.pgm", std::process::id()));
+    std::fs::write(&path, "P2\n# left half\n4 2\n255\n255 200 0 0\n255 128 10 0\n").unwrap();
+    let mask = |invert| Shape::ImageMask {
+        count: 400,
+        path: path.clone(),
+        threshold: 0.5,
+        invert,
+        region: Region::default(),
+    };
+
+    assert!(positions(mask(false)).iter().all(|p| p.x <= WIDTH / 2.0));
+    assert!(positions(mask(true)).iter().all(|p| p.x >= WIDTH / 2.0));
+
+    // Headers claiming huge images are rejected before reading any pixels
+    for header in ["P5\n100000 100000\n255\n", "P2\n18446744073709551615 2\n255\n"] {
+        std::fs::write(&path, header).unwrap();
+        assert!(matches!(
+            apply(vec![spawn(mask(false), VelocityField::Zero)]),
+            Err(PresetError::Invalid(_))
+        ));
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        apply(vec![spawn(mask(false), VelocityField::Zero)]),
+        Err(PresetError::Io(_))
+    ));
+}
+
+#[test]
+fn velocity_fields_turn_around_the_shape_centre() {
+    let disc = Shape::Disc {
+        count: 100,
+        centre: CENTRE,
+        radius: 100.0,
+    };
+    let world = apply(vec![
+        spawn(
+            disc.clone(),
+            VelocityField::Rotational {
+                angular_velocity: 0.5,
+                centre: None,
+            },
+        ),
+        spawn(disc, VelocityField::Radial { speed: -3.0, centre: None }),
+    ])
+    .unwrap();
+    let centre = Vec2::new(WIDTH / 2.0, HEIGHT / 2.0);
+    let (rotating, radial) = world.get_particles().split_at(100);
+    for particle in rotating {
+        let offset = particle.position - centre;
+        let velocity = particle.velocity;
+        assert!((velocity.x * offset.x + velocity.y * offset.y).abs() < 1e-2);
+        assert!((velocity.length() - 0.5 * offset.length()).abs() < 1e-3);
+    }
+    for particle in radial {
+        let inward = (centre - particle.position).normalize();
+        assert!((particle.velocity - inward * 3.0).length() < 1e-3);
+    }
+}
+
+#[test]
+fn invalid_generators_are_rejected() {
+    let ring = Shape::Ring {
+        count: 10,
+        centre: CENTRE,
+        inner_radius: 50.0,
+        outer_radius: 20.0,
+    };
+    let poisson = Shape::PoissonDisc {
+        min_distance: 0.0,
+        max_count: None,
+        region: Region::default(),
+    };
+    let gaussian = Shape::Gaussian {
+        count: 10,
+        centre: CENTRE,
+        std_dev: -1.0,
+    };
+    // Too fine for the lookup grid, even with few points wanted
+    let tiny_poisson = Shape::PoissonDisc {
+        min_distance: 1e-6,
+        max_count: Some(10),
+        region: Region::default(),
+    };
+    let huge_disc = Shape::Disc {
+        count: MAX_SPAWN_PARTICLES + 1,
+        centre: CENTRE,
+        radius: 10.0,
+    };
+    let overflowing_grid = Shape::Grid {
+        columns: usize::MAX,
+        rows: 2,
+        region: Region::default(),
+    };
+    for shape in [ring, poisson, gaussian, tiny_poisson, huge_disc, overflowing_grid] {
+        assert!(
+            matches!(apply(vec![spawn(shape.clone(), VelocityField::Zero)]), Err(PresetError::Invalid(_))),
+            "{:?}",
+            shape
+        );
+    }
+
+    // The limit holds across spawns too
+    let half = Shape::Disc {
+        count: MAX_SPAWN_PARTICLES / 2 + 1,
+        centre: CENTRE,
+        radius: 10.0,
+    };
+    let spawns = vec![spawn(half.clone(), VelocityField::Zero), spawn(half, VelocityField::Zero)];
+    assert!(matches!(apply(spawns), Err(PresetError::Invalid(_))));
+}
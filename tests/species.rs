@@ -0,0 +1,120 @@
+use particle_life_simulator::particle::species::{BLUE, GREEN, NEON_PINK, RED};
+use particle_life_simulator::particle::{Particle, Species, SpeciesId, SpeciesRegistry};
+use particle_life_simulator::simulation::{InteractionMatrix, World, MAX_SPECIES, MIN_SPECIES};
+use particle_life_simulator::utils::math::Vec2;
+
+fn species(name: &str) -> Species {
+    Species::new(name, [1.0, 1.0, 1.0, 1.0], 2.0, 0.5)
+}
+
+#[test]
+fn registered_species_keep_their_ids() {
+    let mut registry = SpeciesRegistry::default();
+    assert_eq!(
+        [RED, BLUE, GREEN, NEON_PINK].map(|id| registry.name(id)),
+        ["Red", "Blue", "Green", "Pink"]
+    );
+
+    let cyan = registry.register(species("Cyan")).unwrap();
+    assert_eq!(cyan, 4);
+    assert_eq!(registry.find("Cyan"), Some(cyan));
+    assert_eq!(registry.find("Blue"), Some(BLUE));
+
+    // Removing moves only the later species down
+    assert_eq!(registry.remove(GREEN).unwrap().name, "Green");
+    assert_eq!(registry.find("Red"), Some(RED));
+    assert_eq!(registry.find("Blue"), Some(BLUE));
+    assert_eq!(registry.find("Pink"), Some(NEON_PINK - 1));
+    assert_eq!(registry.find("Cyan"), Some(cyan - 1));
+    assert_eq!(registry.find("Green"), None);
+    assert!(registry.remove(10).is_none());
+    assert_eq!(registry.len(), 4);
+
+    // Particles take the species multipliers
+    let particle = registry.create_particle(Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0), cyan - 1, 1.0, 4.0);
+    assert_eq!((particle.mass, particle.radius), (2.0, 2.0));
+}
+
+#[test]
+fn registries_stop_at_the_species_limit() {
+    // Every id the registry hands out fits in a SpeciesId
+    assert!(MAX_SPECIES <= SpeciesId::MAX as usize + 1);
+
+    let mut registry = SpeciesRegistry::new();
+    for i in 0..MAX_SPECIES {
+        assert_eq!(registry.register(species(&format!("S{}", i))), Some(i as SpeciesId));
+    }
+    assert_eq!(registry.register(species("Extra")), None);
+    assert_eq!(registry.len(), MAX_SPECIES);
+}
+
+#[test]
+fn world_matrix_follows_species_changes() {
+    let mut world = World::new_with_seed(400.0, 300.0, 1);
+    world.load_preset(1).unwrap();
+    world.get_interaction_matrix_mut().set(BLUE as usize, NEON_PINK as usize, 0.6);
+    let before = world.get_interaction_matrix().clone();
+
+    // A new species gets a zero row and column, keeping the other entries
+    let cyan = world.register_species(species("Cyan")).unwrap();
+    let matrix = world.get_interaction_matrix();
+    assert_eq!(matrix.species_count(), 5);
+    for source in 0..4 {
+        for target in 0..4 {
+            assert_eq!(matrix.get(source, target), before.get(source, target));
+        }
+        assert_eq!(matrix.get(source, cyan as usize), 0.0);
+        assert_eq!(matrix.get(cyan as usize, source), 0.0);
+    }
+
+    // Removing one drops its particles, row and column
+    world.spawn_particle(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0), cyan, 1.0, 2.0);
+    let pink_count = world.get_particles().iter().filter(|p| p.species == NEON_PINK).count();
+    world.remove_species(GREEN).unwrap();
+    let particles = world.get_particles();
+    assert!(particles.iter().all(|p| (p.species as usize) < world.get_species().len()));
+    assert_eq!(particles.iter().filter(|p| p.species == NEON_PINK - 1).count(), pink_count);
+    assert_eq!(particles.iter().filter(|p| p.species == cyan - 1).count(), 1);
+    assert_eq!(world.get_interaction_matrix().species_count(), 4);
+    assert_eq!(world.get_interaction_matrix().get(BLUE as usize, NEON_PINK as usize - 1), 0.6);
+
+    // The matrix never shrinks below the minimum
+    while world.get_species().len() > 1 {
+        world.remove_species(0).unwrap();
+    }
+    assert_eq!(world.get_interaction_matrix().species_count(), MIN_SPECIES);
+}
+
+#[test]
+fn particles_of_unknown_species_are_rejected() {
+    let mut world = World::new_with_seed(400.0, 300.0, 1);
+    let mut registry = SpeciesRegistry::new();
+    registry.register(species("A")).unwrap();
+    registry.register(species("B")).unwrap();
+    world.set_species(registry);
+
+    assert!(world.spawn_particle(Vec2::new(50.0, 50.0), Vec2::new(0.0, 0.0), BLUE, 1.0, 2.0));
+    assert!(!world.spawn_particle(Vec2::new(60.0, 50.0), Vec2::new(0.0, 0.0), GREEN, 1.0, 2.0));
+    assert!(!world.add_particle(Particle::new(Vec2::new(70.0, 50.0), Vec2::new(0.0, 0.0), NEON_PINK, 1.0, 2.0)));
+    assert_eq!(world.particle_count(), 1);
+
+    // Nothing reaches the force pass with a pair the matrix doesn't cover
+    world.update(0.016);
+}
+
+#[test]
+fn small_matrices_grow_to_cover_every_species() {
+    let mut world = World::new_with_seed(400.0, 300.0, 1);
+    world.load_preset(1).unwrap();
+    let mut matrix = InteractionMatrix::new(MIN_SPECIES);
+    matrix.set(0, 1, 0.5);
+    world.set_interaction_matrix(matrix);
+
+    let matrix = world.get_interaction_matrix();
+    assert_eq!(matrix.species_count(), world.get_species().len());
+    assert_eq!((matrix.get(0, 1), matrix.get(3, 2)), (0.5, 0.0));
+
+    // The world steps and its snapshots load back
+    world.update(0.016);
+    assert!(World::from_snapshot(world.snapshot()).is_ok());
+}
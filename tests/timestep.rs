@@ -0,0 +1,59 @@
+use particle_life_simulator::simulation::timestep::MAX_SUBSTEPS;
+use particle_life_simulator::simulation::FixedTimestep;
+
+// Steps run over `frames` frames of `frame_time` seconds each
+fn steps_over(timestep: &mut FixedTimestep, frames: usize, frame_time: f32) -> u32 {
+    (0..frames).map(|_| timestep.advance(frame_time)).sum()
+}
+
+#[test]
+fn step_count_follows_elapsed_time_not_frame_rate() {
+    // One second at 30, 60 and 144 frames per second
+    for fps in [30, 60, 144] {
+        let mut timestep = FixedTimestep::new(1.0 / 60.0, 2);
+        let steps = steps_over(&mut timestep, fps, 1.0 / fps as f32);
+        assert!((119..=120).contains(&steps), "{} fps: {} steps", fps, steps);
+        assert!((0.0..=1.0).contains(&timestep.alpha()));
+    }
+}
+
+#[test]
+fn leftover_time_carries_over() {
+    let mut timestep = FixedTimestep::new(0.01, 1);
+    assert_eq!(timestep.advance(0.025), 2);
+    assert!((timestep.alpha() - 0.5).abs() < 1e-3, "{}", timestep.alpha());
+    assert_eq!(timestep.advance(0.005), 1);
+    assert!(timestep.alpha() < 1e-3);
+}
+
+#[test]
+fn time_scale_changes_the_number_of_steps_not_their_size() {
+    let mut timestep = FixedTimestep::new(1.0 / 60.0, 1);
+    let step_dt = timestep.step_dt();
+    for (scale, expected) in [(0.25, 15), (1.0, 60), (4.0, 240)] {
+        timestep.reset();
+        timestep.set_time_scale(scale);
+        let steps = steps_over(&mut timestep, 60, 1.0 / 60.0);
+        assert!(steps.abs_diff(expected) <= 1, "{}x: {} steps", scale, steps);
+        assert_eq!(timestep.step_dt(), step_dt);
+    }
+}
+
+#[test]
+fn substeps_split_each_frame() {
+    let mut timestep = FixedTimestep::new(1.0 / 60.0, 4);
+    assert!((timestep.step_dt() - 1.0 / 240.0).abs() < 1e-9);
+    assert_eq!(timestep.advance(1.0 / 60.0 + 1e-6), 4);
+
+    timestep.set_substeps(0);
+    assert_eq!(timestep.substeps(), 1);
+    timestep.set_substeps(1000);
+    assert_eq!(timestep.substeps(), MAX_SUBSTEPS);
+}
+
+#[test]
+fn long_frames_do_not_cause_a_burst_of_steps() {
+    let mut timestep = FixedTimestep::new(1.0 / 60.0, 1);
+    let steps = timestep.advance(5.0);
+    assert!(steps <= 6, "{} steps", steps);
+}